@@ -0,0 +1,50 @@
+//! Throughput of the simulator on large networks, in steps per second:
+//! each iteration simulates the peers until all of them decide one height.
+//!
+//! Run with `cargo bench`.
+
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use malachite_simulator::context::height::BaseHeight;
+use malachite_simulator::{
+    SimulationReport, SimulatorBuilder, StopConditions, StopReason, Workload,
+};
+
+// Signatures are mocked, as verifying them would dominate the measurements.
+fn decide_one_height(peers: u32) -> SimulationReport {
+    let (mut n, mut states, _proposals, _decisions) = SimulatorBuilder::new(peers)
+        .seed(1)
+        .mock_signatures(true)
+        .workload(Workload::Sequential(45))
+        .build();
+
+    let stop = StopConditions {
+        height: Some(BaseHeight(0)),
+        ..Default::default()
+    };
+    n.run(&mut states, stop)
+}
+
+fn large_networks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decide_one_height");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(30));
+
+    for peers in [100, 300] {
+        // Runs are deterministic, so every iteration takes as many steps as this one
+        let report = decide_one_height(peers);
+        assert_eq!(report.reason, StopReason::Height);
+        group.throughput(Throughput::Elements(report.steps));
+
+        group.bench_with_input(BenchmarkId::from_parameter(peers), &peers, |b, peers| {
+            b.iter(|| decide_one_height(*peers))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, large_networks);
+criterion_main!(benches);
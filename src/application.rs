@@ -1,21 +1,30 @@
-use tracing::{debug, info, span, trace, Level};
+use std::collections::HashSet;
+use std::time::Duration;
+use tracing::{debug, info, span, trace, warn, Level};
 
 use malachite_core_consensus::{
     ConsensusMsg, Effect, Error, Input, Params, ProposedValue, Resumable, Resume,
     SignedConsensusMsg, State, ValueToPropose,
 };
 use malachite_core_types::{
-    CommitCertificate, Height, Round, SignedMessage, SigningProvider, Timeout, TimeoutKind,
-    Validator, Validity, ValueOrigin,
+    CertificateError, CommitCertificate, Context, Height, Round, SignedMessage, SignedProposalPart,
+    SigningProvider, ThresholdParams, Timeout, Validator, ValidatorSet, Validity, ValueOrigin,
+    VoteSet,
 };
 use malachite_metrics::Metrics;
 
+use crate::byzantine::{is_odd, ByzantineBehaviour};
 use crate::context::address::BasePeerAddress;
 use crate::context::height::BaseHeight;
 use crate::context::peer_set::BasePeerSet;
+use crate::context::signing_provider::BaseSigningProvider;
+use crate::context::value::BaseValue;
 use crate::context::BaseContext;
 use crate::decision::Decision;
-use crate::simulator::{DecisionsSender, Envelope, NetSender, ProposalsReceiver};
+use crate::simulator::{DecisionsSender, Envelope, Message, NetSender, ProposalsReceiver};
+use crate::streaming::{self, PartStreams};
+use crate::timers::Timers;
+use crate::validators::ValidatorSchedule;
 
 /// An application is the deterministic state machine executing
 /// at a specific peer.
@@ -23,7 +32,7 @@ use crate::simulator::{DecisionsSender, Envelope, NetSender, ProposalsReceiver};
 /// It contains:
 ///
 /// (1) a [`NetSender`], which it uses to transmit
-/// [`Input`]s to itself or to application instances
+/// [`Message`]s to itself or to application instances
 /// running at other peers.
 ///
 /// (2) a [`ProposalsReceiver`] which the app uses to
@@ -34,6 +43,10 @@ use crate::simulator::{DecisionsSender, Envelope, NetSender, ProposalsReceiver};
 /// outside environment each [`Decision`] which
 /// this local application took.
 ///
+/// (4) the [`Timers`] which consensus at this peer
+/// scheduled, each of them eventually elapsing as an
+/// [`Input::TimeoutElapsed`] unless consensus cancels it.
+///
 /// The application is a wrapper over the [`malachite_core_consensus`] library.
 /// In particular, the application calls [`malachite_core_consensus::process!`]
 /// with certain [`Input`]s and handles [`Effect`]s produced by the
@@ -41,7 +54,7 @@ use crate::simulator::{DecisionsSender, Envelope, NetSender, ProposalsReceiver};
 pub struct Application {
     pub peer_id: BasePeerAddress,
 
-    /// Send [`Input`]s to the application running at self and other peers.
+    /// Send [`Message`]s to the application running at self and other peers.
     pub network_tx: NetSender,
 
     // Send [`Decision`]s to the environment, i.e., the [`System`].
@@ -49,17 +62,37 @@ pub struct Application {
 
     // Receive the values that this application will propose to consensus
     pub proposal_rx: ProposalsReceiver,
+
+    // The timeouts which consensus scheduled and did not yet cancel
+    pub timers: Timers,
+
+    // The simulated time at which this peer started its current height
+    pub height_started_at: Duration,
+
+    // If set, every signature is valid without any verification, which is faster
+    pub mock_signature_verification: bool,
+
+    // How this peer deviates from the protocol when publishing messages, if at all
+    pub byzantine: ByzantineBehaviour,
+
+    // The validators of each height. The initial validator set in the params
+    // of the peer lists every peer in the simulation instead, whom messages go to.
+    pub validators: ValidatorSchedule,
+
+    // The parts of the values which other peers stream, until each value is complete
+    pub streams: PartStreams,
 }
 
 impl Application {
-    pub fn init(&self, initial_validator_set: BasePeerSet) {
-        let input = Input::StartHeight(BaseHeight(0), initial_validator_set);
+    pub fn init(&self) {
+        let height = BaseHeight(0);
+        let input = Input::StartHeight(height, self.validators.at(height).clone());
 
         let envelope = Envelope {
             // Send this envelope to self
             destination: self.peer_id,
             source: self.peer_id,
-            payload: input,
+            payload: Message::Input(input),
         };
 
         // This envelope will later be used in apply_input.
@@ -68,41 +101,77 @@ impl Application {
 
     // Wrapper over `process!` macro to work around the confusion
     // in return types due to the loop { } inside that macro.
+    // The `now` argument is the time elapsed since the simulator started.
+    #[allow(clippy::result_large_err)]
     pub fn apply_input(
-        &self,
+        &mut self,
         input: Input<BaseContext>,
         peer_params: &Params<BaseContext>,
         metrics: &Metrics,
         peer_state: &mut State<BaseContext>,
         ctx: &BaseContext,
+        now: Duration,
     ) -> Result<(), Error<BaseContext>> {
         malachite_core_consensus::process!(
             input: input,
             state: peer_state,
             metrics: metrics,
             with: effect =>
-                self.handle_effect(peer_params, effect, ctx)
+                self.handle_effect(peer_params, effect, ctx, now)
         )
     }
 
-    fn handle_schedule_timeout(&self, t: Timeout) -> Result<Resume<BaseContext>, String> {
-        let Timeout { round: _, kind } = t;
+    /// Applies the input in `message`, or buffers the part in it.
+    /// The part of a value which completes it applies as an [`Input::ProposedValue`].
+    #[allow(clippy::result_large_err)]
+    pub fn apply_message(
+        &mut self,
+        message: Message,
+        peer_params: &Params<BaseContext>,
+        metrics: &Metrics,
+        peer_state: &mut State<BaseContext>,
+        ctx: &BaseContext,
+        now: Duration,
+    ) -> Result<(), Error<BaseContext>> {
+        let input = match message {
+            Message::Input(input) => input,
+            Message::ProposalPart(part) => {
+                let Some(value) = self.handle_proposal_part(part, ctx) else {
+                    return Ok(());
+                };
+                Input::ProposedValue(value, ValueOrigin::Consensus)
+            }
+        };
 
-        // Special case to handle.
-        // If it's a timeout for kind Commit, then handle this timeout instantly.
-        // Signal to self that the timeout has elapsed.
-        // This will prompt consensus to provide the effect `Decide` afterward.
-        if kind == TimeoutKind::Commit {
-            debug!("triggering TimeoutElapsed for Commit");
+        self.apply_input(input, peer_params, metrics, peer_state, ctx, now)
+    }
+
+    /// Signals to self every timeout that elapsed by `now`.
+    /// Consensus handles each of them later, as an [`Input::TimeoutElapsed`].
+    /// For instance, the Commit timeout prompts consensus to provide the
+    /// effect `Decide` afterward.
+    pub fn fire_timeouts(&mut self, now: Duration) {
+        for t in self.timers.expired(now) {
+            debug!(peer = %self.peer_id, timeout = %t, "triggering TimeoutElapsed");
 
             self.network_tx
                 .send(Envelope {
                     source: self.peer_id,
                     destination: self.peer_id,
-                    payload: Input::TimeoutElapsed(t),
+                    payload: Message::Input(Input::TimeoutElapsed(t)),
                 })
                 .unwrap();
         }
+    }
+
+    fn handle_schedule_timeout(
+        &mut self,
+        t: Timeout,
+        now: Duration,
+    ) -> Result<Resume<BaseContext>, String> {
+        // Nothing is sent yet, the timeout elapses at a later step
+        // of the simulator, see `fire_timeouts`.
+        self.timers.schedule(t, now);
 
         Ok(Resume::Continue)
     }
@@ -111,11 +180,33 @@ impl Application {
         &self,
         v: SignedConsensusMsg<BaseContext>,
         peer_params: &Params<BaseContext>,
+        context: &BaseContext,
     ) -> Result<Resume<BaseContext>, String> {
+        // Peers which are not validators at the height of a vote follow
+        // consensus without taking part in it
+        if let SignedConsensusMsg::Vote(sv) = &v {
+            if !self.validators.is_validator(&self.peer_id, sv.height) {
+                trace!(peer = %self.peer_id, height = %sv.height, "not a validator, not voting");
+                return Ok(Resume::Continue);
+            }
+        }
+
+        // A Byzantine peer sends other peers tampered messages, but keeps
+        // the honest ones for itself
+        let [even, odd] = self.byzantine.tamper(&v, context.signing_provider());
+
         // Push the signed consensus message into the inbox of all peers.
         // That's all that broadcast entails.
-        for destination in peer_params.initial_validator_set.peers.iter() {
+        for destination in peer_params.initial_validator_set.peers().iter() {
             let destination_addr = destination.address();
+            let v = if destination_addr == &self.peer_id {
+                &v
+            } else if is_odd(destination_addr) {
+                &odd
+            } else {
+                &even
+            };
+
             match v {
                 SignedConsensusMsg::Vote(ref sv) => {
                     // Note: No need to broadcast the vote to self
@@ -124,7 +215,7 @@ impl Application {
                             .send(Envelope {
                                 source: self.peer_id,
                                 destination: *destination_addr,
-                                payload: Input::Vote(sv.clone()),
+                                payload: Message::Input(Input::Vote(sv.clone())),
                             })
                             .unwrap()
                     }
@@ -134,27 +225,33 @@ impl Application {
                         .send(Envelope {
                             source: self.peer_id,
                             destination: *destination_addr,
-                            payload: Input::Proposal(sp.clone()),
+                            payload: Message::Input(Input::Proposal(sp.clone())),
                         })
                         .unwrap();
 
+                    // The proposer streams the value in parts, if consensus
+                    // expects parts, see `stream_value`
+                    if peer_params.value_payload.include_parts() {
+                        continue;
+                    }
+
                     // Todo: This was not intuitive to find - source of confusion
                     self.network_tx
                         .send(Envelope {
                             source: self.peer_id,
                             destination: *destination_addr,
-                            payload: Input::ProposedValue(
+                            payload: Message::Input(Input::ProposedValue(
                                 ProposedValue {
                                     height: sp.height,
                                     round: sp.round,
-                                    valid_round: Round::Nil,
+                                    valid_round: sp.pol_round,
                                     proposer: sp.proposer,
                                     value: sp.value,
                                     validity: Validity::Valid,
                                     extension: None,
                                 },
                                 ValueOrigin::Consensus,
-                            ),
+                            )),
                         })
                         .unwrap();
                 }
@@ -163,14 +260,86 @@ impl Application {
         Ok(Resume::Continue)
     }
 
-    fn handle_decide(
+    // Streams a value which this peer proposes to every other peer, as the
+    // parts of a proposal, if consensus expects parts at all.
+    // Consensus at the proposer already knows the value.
+    // A Byzantine peer streams to each subset of peers the variant of the
+    // value in the proposal it sends them, see `handle_publish`.
+    fn stream_value(
         &self,
-        certificate: CommitCertificate<BaseContext>,
+        value: ProposedValue<BaseContext>,
         peer_params: &Params<BaseContext>,
+        context: &BaseContext,
+    ) {
+        if !peer_params.value_payload.include_parts() {
+            return;
+        }
+
+        let [even, odd] = self.byzantine.tamper_value(&value).map(|v| {
+            streaming::split(&v)
+                .into_iter()
+                .map(|part| context.signing_provider().sign_proposal_part(part))
+                .collect::<Vec<_>>()
+        });
+
+        for (even, odd) in even.iter().zip(odd.iter()) {
+            for destination in peer_params.initial_validator_set.peers().iter() {
+                let destination_addr = destination.address();
+                if destination_addr != &self.peer_id {
+                    let part = if is_odd(destination_addr) { odd } else { even };
+                    self.network_tx
+                        .send(Envelope {
+                            source: self.peer_id,
+                            destination: *destination_addr,
+                            payload: Message::ProposalPart(part.clone()),
+                        })
+                        .unwrap();
+                }
+            }
+        }
+    }
+
+    // Buffers a part which another peer streams, once its signature verifies.
+    // Returns the value of the stream of the part, if the part completes it.
+    fn handle_proposal_part(
+        &mut self,
+        part: SignedProposalPart<BaseContext>,
+        context: &BaseContext,
+    ) -> Option<ProposedValue<BaseContext>> {
+        trace!(peer = %self.peer_id, "received {}", part.message);
+
+        if !self.mock_signature_verification {
+            let proposer = part.message.proposer;
+            let peer_set = self.validators.at(part.message.height);
+            let valid = peer_set.get_by_address(&proposer).is_some_and(|peer| {
+                context.signing_provider().verify_signed_proposal_part(
+                    &part.message,
+                    &part.signature,
+                    peer.public_key(),
+                )
+            });
+            if !valid {
+                warn!(%proposer, "invalid signature {}", part.message);
+                return None;
+            }
+        }
+
+        let value = self.streams.insert(part.message);
+        if let Some(v) = &value {
+            debug!(peer = %self.peer_id, height = %v.height, round = %v.round, "value streamed in full");
+        }
+
+        value
+    }
+
+    fn handle_decide(
+        &mut self,
+        certificate: CommitCertificate<BaseContext>,
+        now: Duration,
     ) -> Result<Resume<BaseContext>, String> {
-        println!("decision arrived!");
+        debug!(peer = %self.peer_id, height = %certificate.height, "decision arrived");
         for s in certificate.aggregated_signature.signatures.iter() {
-            println!("signature from {:?} with ext {:?}", s.address, s.extension);
+            trace!(signer = %s.address, extension = ?s.extension, "signature in the certificate");
         }
 
         // Let the top-level system/environment know about this decision
@@ -179,18 +348,25 @@ impl Application {
                 peer: self.peer_id,
                 value_id: certificate.value_id,
                 height: certificate.height,
+                round: certificate.round,
+                time: now,
+                elapsed: now - self.height_started_at,
             })
             .expect("unable to send a decision");
 
-        // Proceed to the next height
-        let val_set = peer_params.initial_validator_set.clone();
+        // Parts of values for this height are no use anymore
+        self.streams.prune(certificate.height);
+
+        // Proceed to the next height, with its validators
+        let height = certificate.height.increment();
+        let val_set = self.validators.at(height).clone();
 
         // Register the input in the inbox of this peer
         self.network_tx
             .send(Envelope {
                 source: self.peer_id,
                 destination: self.peer_id,
-                payload: Input::StartHeight(certificate.height.increment(), val_set),
+                payload: Message::Input(Input::StartHeight(height, val_set)),
             })
             .unwrap();
 
@@ -201,9 +377,34 @@ impl Application {
     // The app creates a value and provides it as input to Malachite
     // in the form of a `ValueToPropose` variant.
     // Register this input in the inbox of the current validator.
-    // If no value is available, the application blocks waiting.
-    fn handle_get_value(&self, h: BaseHeight, r: Round) -> Result<Resume<BaseContext>, String> {
-        let value = self.proposal_rx.try_recv().map_err(|_| "no value")?;
+    // If no value is available, the application proposes nothing and
+    // the Propose timeout will eventually move consensus forward.
+    fn handle_get_value(
+        &self,
+        h: BaseHeight,
+        r: Round,
+        peer_params: &Params<BaseContext>,
+        context: &BaseContext,
+    ) -> Result<Resume<BaseContext>, String> {
+        let Ok(value) = self.proposal_rx.try_recv() else {
+            warn!(height = %h, round = %r, "no value to propose");
+
+            return Ok(Resume::Continue);
+        };
+
+        self.stream_value(
+            ProposedValue {
+                height: h,
+                round: r,
+                valid_round: Round::Nil,
+                proposer: self.peer_id,
+                value,
+                validity: Validity::Valid,
+                extension: None,
+            },
+            peer_params,
+            context,
+        );
 
         let input_value = ValueToPropose {
             height: h,
@@ -216,18 +417,131 @@ impl Application {
             .send(Envelope {
                 source: self.peer_id,
                 destination: self.peer_id,
-                payload: Input::Propose(input_value),
+                payload: Message::Input(Input::Propose(input_value)),
             })
             .unwrap();
 
         Ok(Resume::Continue)
     }
 
-    fn handle_effect(
+    // Verifies the signature of a vote or proposal against the public key of its
+    // author, as registered in the validator set of the height of the message.
+    // Messages from peers outside that set are invalid.
+    fn handle_verify_signature(
+        &self,
+        m: SignedMessage<BaseContext, ConsensusMsg<BaseContext>>,
+        context: &BaseContext,
+    ) -> bool {
+        if self.mock_signature_verification {
+            return true;
+        }
+
+        let (author, height) = match &m.message {
+            ConsensusMsg::Vote(v) => (v.voter, v.height),
+            ConsensusMsg::Proposal(p) => (p.proposer, p.height),
+        };
+        let peer_set = self.validators.at(height);
+        let Some(peer) = peer_set.get_by_address(&author) else {
+            warn!(%author, "signature from a peer outside the peer set");
+            return false;
+        };
+
+        let valid = match &m.message {
+            ConsensusMsg::Vote(v) => {
+                context
+                    .signing_provider()
+                    .verify_signed_vote(v, &m.signature, peer.public_key())
+            }
+            ConsensusMsg::Proposal(p) => context.signing_provider().verify_signed_proposal(
+                p,
+                &m.signature,
+                peer.public_key(),
+            ),
+        };
+
+        if !valid {
+            warn!(%author, "invalid signature {}", pretty_verify_signature(&m));
+        }
+
+        valid
+    }
+
+    // Verifies a commit certificate which this peer received out-of-band,
+    // i.e., not by taking part in the consensus instance that produced it.
+    #[allow(clippy::result_large_err)]
+    fn handle_verify_certificate(
         &self,
+        certificate: &CommitCertificate<BaseContext>,
+        validator_set: &BasePeerSet,
+        thresholds: ThresholdParams,
+        context: &BaseContext,
+    ) -> Result<(), CertificateError<BaseContext>> {
+        let signing_provider =
+            (!self.mock_signature_verification).then_some(context.signing_provider());
+
+        let result =
+            verify_commit_certificate(certificate, validator_set, thresholds, signing_provider);
+
+        if let Err(e) = &result {
+            warn!(height = %certificate.height, round = %certificate.round, "invalid certificate: {e}");
+        }
+
+        result
+    }
+
+    // Consensus is stuck in the Prevote or Precommit step of round `r`.
+    // Ask all the other peers for the votes they have for that round.
+    // The request identifier records who is asking, so that peers know
+    // where to send their response.
+    fn handle_get_vote_set(
+        &self,
+        h: BaseHeight,
+        r: Round,
+        peer_params: &Params<BaseContext>,
+    ) -> Result<Resume<BaseContext>, String> {
+        let request_id = vote_set_request_id(self.peer_id, h, r);
+
+        for destination in peer_params.initial_validator_set.peers().iter() {
+            let destination_addr = destination.address();
+            if destination_addr != &self.peer_id {
+                self.network_tx
+                    .send(Envelope {
+                        source: self.peer_id,
+                        destination: *destination_addr,
+                        payload: Message::Input(Input::VoteSetRequest(request_id.clone(), h, r)),
+                    })
+                    .unwrap();
+            }
+        }
+
+        Ok(Resume::Continue)
+    }
+
+    fn handle_send_vote_set_response(
+        &self,
+        request_id: String,
+        vote_set: VoteSet<BaseContext>,
+    ) -> Result<Resume<BaseContext>, String> {
+        let requester = vote_set_requester(&request_id)
+            .ok_or_else(|| format!("malformed vote set request id {request_id}"))?;
+
+        self.network_tx
+            .send(Envelope {
+                source: self.peer_id,
+                destination: requester,
+                payload: Message::Input(Input::VoteSetResponse(vote_set)),
+            })
+            .unwrap();
+
+        Ok(Resume::Continue)
+    }
+
+    fn handle_effect(
+        &mut self,
         peer_params: &Params<BaseContext>,
         effect: Effect<BaseContext>,
         context: &BaseContext,
+        now: Duration,
     ) -> Result<Resume<BaseContext>, String> {
         assert_eq!(peer_params.address, self.peer_id);
 
@@ -239,73 +553,97 @@ impl Application {
             Effect::ResetTimeouts(c) => {
                 trace!("ResetTimeouts");
 
+                self.timers.reset();
+
                 Ok(c.resume_with(()))
             }
             Effect::CancelAllTimeouts(c) => {
                 trace!("CancelAllTimeouts");
 
+                self.timers.cancel_all();
+
                 Ok(c.resume_with(()))
             }
-            Effect::CancelTimeout(_, c) => {
-                trace!("CancelTimeout");
+            Effect::CancelTimeout(t, c) => {
+                trace!("CancelTimeout {}", t);
+
+                self.timers.cancel(&t);
 
                 Ok(c.resume_with(()))
             }
             Effect::ScheduleTimeout(t, c) => {
                 trace!("ScheduleTimeout {}", t);
 
-                let _res = self.handle_schedule_timeout(t).unwrap();
+                let _res = self.handle_schedule_timeout(t, now).unwrap();
 
                 Ok(c.resume_with(()))
             }
-            Effect::StartRound(_, _, _, c) => {
+            Effect::StartRound(_, r, _, c) => {
                 trace!("StartRound");
 
-                // Nothing in particular to keep track of
+                // Only keep track of when the height started
+                if r == Round::new(0) {
+                    self.height_started_at = now;
+                }
 
                 Ok(c.resume_with(()))
             }
             Effect::Publish(v, c) => {
                 info!("Publish {}", pretty_publish(&v));
 
-                let _ = self.handle_publish(v, peer_params).unwrap();
+                let _ = self.handle_publish(v, peer_params, context).unwrap();
 
                 Ok(c.resume_with(()))
             }
             Effect::GetValue(h, r, _, c) => {
                 trace!("GetValue");
 
-                let _ = self.handle_get_value(h, r).unwrap();
+                let _ = self.handle_get_value(h, r, peer_params, context).unwrap();
 
                 Ok(c.resume_with(()))
             }
             Effect::GetValidatorSet(h, c) => {
-                info!("GetValidatorSet({}); providing the default", h);
+                info!("GetValidatorSet({})", h);
 
-                // Assumption: validator sets do not change.
-                let val_set = peer_params.initial_validator_set.clone();
+                let val_set = self.validators.at(h).clone();
 
                 Ok(c.resume_with(Some(val_set)))
             }
             Effect::VerifySignature(m, _, c) => {
-                trace!("VerifySignature {}", pretty_verify_signature(m));
+                trace!("VerifySignature {}", pretty_verify_signature(&m));
 
-                // Consider implementing this to be able to capture more realistic
-                // conditions.
-                // Not required right now, given the current use of this application.
+                let valid = self.handle_verify_signature(m, context);
 
-                Ok(c.resume_with(true))
+                Ok(c.resume_with(valid))
             }
             Effect::Decide(certificate, c) => {
                 trace!("Decide");
 
                 // TODO: No need to return resume from here
-                let _ = self.handle_decide(certificate, peer_params).unwrap();
+                let _ = self.handle_decide(certificate, now).unwrap();
 
                 Ok(c.resume_with(()))
             }
-            Effect::RestreamValue(_, _, _, _, _, _c) => {
-                panic!("unimplemented arm Effect::RestreamValue in match effect");
+            Effect::RestreamValue(h, r, vr, proposer, value_id, c) => {
+                trace!("RestreamValue({}, {}, {})", h, r, vr);
+
+                // Re-proposing a valid value streams it again, for this round.
+                // The identifier of a value is the value itself.
+                self.stream_value(
+                    ProposedValue {
+                        height: h,
+                        round: r,
+                        valid_round: vr,
+                        proposer,
+                        value: BaseValue(value_id.0),
+                        validity: Validity::Valid,
+                        extension: None,
+                    },
+                    peer_params,
+                    context,
+                );
+
+                Ok(c.resume_with(()))
             }
             Effect::PersistMessage(_, c) => {
                 // No support for crash-recovery
@@ -316,27 +654,53 @@ impl Application {
                 Ok(c.resume_with(()))
             }
             Effect::SignVote(v, c) => {
-                let sv = context.signing_provider.sign_vote(v);
+                let sv = context.signing_provider().sign_vote(v);
 
                 Ok(c.resume_with(sv))
             }
             Effect::SignProposal(p, c) => {
-                let sp = context.signing_provider.sign_proposal(p);
+                let sp = context.signing_provider().sign_proposal(p);
 
                 Ok(c.resume_with(sp))
             }
-            Effect::GetVoteSet(_, _, _) => {
-                // Not needed
-                // The app will never go into round > 0
-                panic!("unimplemented arm Effect::GetVoteSet in match effect")
+            Effect::GetVoteSet(h, r, c) => {
+                warn!("GetVoteSet({}, {})", h, r);
+
+                let _ = self.handle_get_vote_set(h, r, peer_params).unwrap();
+
+                Ok(c.resume_with(()))
             }
-            Effect::SendVoteSetResponse(_, _, _, _, _) => {
-                // Not needed
-                // The app will never go into round > 0
-                panic!("unimplemented arm Effect::SendVoteSetResponse in match effect")
+            Effect::SendVoteSetResponse(request_id, h, r, vote_set, c) => {
+                trace!(
+                    "SendVoteSetResponse({}, {}, {}) with {} votes",
+                    request_id,
+                    h,
+                    r,
+                    vote_set.len()
+                );
+
+                let _ = self
+                    .handle_send_vote_set_response(request_id, vote_set)
+                    .unwrap();
+
+                Ok(c.resume_with(()))
             }
-            Effect::VerifyCertificate(_, _, _, _) => {
-                panic!("unimplemented arm Effect::VerifyCertificate in match effect")
+            Effect::VerifyCertificate(certificate, validator_set, thresholds, c) => {
+                trace!(
+                    "VerifyCertificate({}, {}) with {} signatures",
+                    certificate.height,
+                    certificate.round,
+                    certificate.aggregated_signature.signatures.len()
+                );
+
+                let validity = self.handle_verify_certificate(
+                    &certificate,
+                    &validator_set,
+                    thresholds,
+                    context,
+                );
+
+                Ok(c.resume_with(validity))
             }
         }
     }
@@ -349,9 +713,151 @@ fn pretty_publish(v: &SignedConsensusMsg<BaseContext>) -> String {
     }
 }
 
-fn pretty_verify_signature(m: SignedMessage<BaseContext, ConsensusMsg<BaseContext>>) -> String {
-    match m.message {
+fn pretty_verify_signature(m: &SignedMessage<BaseContext, ConsensusMsg<BaseContext>>) -> String {
+    match &m.message {
         ConsensusMsg::Vote(v) => v.to_string(),
         ConsensusMsg::Proposal(p) => p.to_string(),
     }
 }
+
+/// Verifies that `certificate` carries a valid signature from each of its signers,
+/// that all signers belong to `validator_set` and sign at most once, and that
+/// together they hold a quorum of the voting power, as per `thresholds`.
+/// Returns the first problem found.
+///
+/// Without a `signing_provider`, signatures are considered valid without
+/// verifying them, as when the simulator mocks signature verification.
+#[allow(clippy::result_large_err)]
+pub fn verify_commit_certificate(
+    certificate: &CommitCertificate<BaseContext>,
+    validator_set: &BasePeerSet,
+    thresholds: ThresholdParams,
+    signing_provider: Option<&BaseSigningProvider>,
+) -> Result<(), CertificateError<BaseContext>> {
+    let mut signers = HashSet::new();
+    let mut signed_voting_power = 0;
+
+    for commit_sig in certificate.aggregated_signature.signatures.iter() {
+        let Some(validator) = validator_set.get_by_address(&commit_sig.address) else {
+            return Err(CertificateError::UnknownValidator(commit_sig.clone()));
+        };
+
+        // There is no dedicated error for a repeated signer: only the first
+        // signature of each validator is valid in the certificate.
+        if !signers.insert(commit_sig.address) {
+            return Err(CertificateError::InvalidSignature(commit_sig.clone()));
+        }
+
+        signed_voting_power += match signing_provider {
+            Some(sp) => sp.verify_commit_signature(certificate, commit_sig, validator)?,
+            None => validator.voting_power(),
+        };
+    }
+
+    let total_voting_power = validator_set.total_voting_power();
+    if !thresholds
+        .quorum
+        .is_met(signed_voting_power, total_voting_power)
+    {
+        return Err(CertificateError::NotEnoughVotingPower {
+            signed: signed_voting_power,
+            total: total_voting_power,
+            expected: thresholds.quorum.min_expected(total_voting_power),
+        });
+    }
+
+    Ok(())
+}
+
+// Identifies a vote set request by the peer asking, the height and the round.
+fn vote_set_request_id(requester: BasePeerAddress, h: BaseHeight, r: Round) -> String {
+    format!("{}/{}/{}", requester.0, h, r)
+}
+
+fn vote_set_requester(request_id: &str) -> Option<BasePeerAddress> {
+    let requester = request_id.split('/').next()?;
+
+    requester.parse().ok().map(BasePeerAddress::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use malachite_core_types::{
+        CertificateError, CommitCertificate, NilOrVal, Round, SigningProvider, VoteType,
+    };
+
+    use crate::application::verify_commit_certificate;
+    use crate::context::address::BasePeerAddress;
+    use crate::context::height::BaseHeight;
+    use crate::context::peer_set::BasePeerSet;
+    use crate::context::signing_provider::BaseSigningProvider;
+    use crate::context::value::BaseValueId;
+    use crate::context::vote::BaseVote;
+    use crate::context::BaseContext;
+
+    // A certificate for value 45 at height 3, round 1, signed by the given peers.
+    fn certificate(signers: &[u32]) -> CommitCertificate<BaseContext> {
+        let precommits = signers
+            .iter()
+            .map(|i| {
+                BaseSigningProvider::derive(0, *i).sign_vote(BaseVote {
+                    vote_type: VoteType::Precommit,
+                    height: BaseHeight(3),
+                    value_id: NilOrVal::Val(BaseValueId(45)),
+                    round: Round::new(1),
+                    voter: BasePeerAddress(*i),
+                    extension: None,
+                })
+            })
+            .collect();
+
+        CommitCertificate::new(BaseHeight(3), Round::new(1), BaseValueId(45), precommits)
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn certificates_need_a_quorum_of_distinct_valid_signers() {
+        let sp = BaseSigningProvider::derive(0, 0);
+        let set = BasePeerSet::new(
+            (0..4)
+                .map(|i| BaseSigningProvider::derive(0, i).public_key())
+                .collect(),
+        );
+        let verify = |c: &CommitCertificate<BaseContext>| {
+            verify_commit_certificate(c, &set, Default::default(), Some(&sp))
+        };
+
+        assert!(verify(&certificate(&[0, 1, 2])).is_ok());
+
+        assert!(matches!(
+            verify(&certificate(&[0, 1])),
+            Err(CertificateError::NotEnoughVotingPower {
+                signed: 2,
+                total: 4,
+                ..
+            })
+        ));
+
+        // Signing twice does not count twice
+        assert!(matches!(
+            verify(&certificate(&[0, 1, 1])),
+            Err(CertificateError::InvalidSignature(s)) if s.address == BasePeerAddress(1)
+        ));
+
+        assert!(matches!(
+            verify(&certificate(&[0, 1, 2, 4])),
+            Err(CertificateError::UnknownValidator(s)) if s.address == BasePeerAddress(4)
+        ));
+
+        // A signature over another value
+        let mut forged = certificate(&[0, 1, 2]);
+        forged.value_id = BaseValueId(46);
+        assert!(matches!(
+            verify(&forged),
+            Err(CertificateError::InvalidSignature(_))
+        ));
+
+        // Unless signatures are mocked
+        assert!(verify_commit_certificate(&forged, &set, Default::default(), None).is_ok());
+    }
+}
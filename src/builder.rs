@@ -0,0 +1,259 @@
+/// Configuration of a [`Simulator`] in one place, before it starts.
+///
+use malachite_core_types::VotingPower;
+
+use crate::byzantine::ByzantineBehaviour;
+use crate::context::address::BasePeerAddress;
+use crate::context::height::BaseHeight;
+use crate::context::peer::BASE_VOTING_POWER;
+use crate::context::BaseContext;
+use crate::liveness::StallLimits;
+use crate::observer::Observer;
+use crate::partition::PartitionSchedule;
+use crate::simulator::{
+    ConsensusParams, DecisionsReceiver, LinkFaults, NetworkModel, ProposalsSender, Simulator,
+    States, Workload,
+};
+use crate::timers::TimeoutDurations;
+
+/// Builds a [`Simulator`], where all randomness derives from the seed,
+/// see [`Simulator::with_seed`].
+///
+/// Anything the builder leaves out keeps the default of the simulator,
+/// and the setters of the simulator still apply once it is built.
+pub struct SimulatorBuilder {
+    voting_powers: Vec<VotingPower>,
+    validator_changes: Vec<(BaseHeight, Vec<(BasePeerAddress, VotingPower)>)>,
+    seed: u64,
+    context: BaseContext,
+    consensus: ConsensusParams,
+    timeouts: TimeoutDurations,
+    mock_signatures: bool,
+    faults: LinkFaults,
+    links: Vec<(BasePeerAddress, BasePeerAddress, LinkFaults)>,
+    partitions: Option<PartitionSchedule>,
+    byzantine: Vec<(BasePeerAddress, ByzantineBehaviour)>,
+    workload: Workload,
+    stall_limits: StallLimits,
+    record_trace: bool,
+    observers: Vec<Box<dyn Observer>>,
+}
+
+impl SimulatorBuilder {
+    /// A simulator of `peers` peers, each of them a validator with
+    /// the voting power [`BASE_VOTING_POWER`].
+    pub fn new(peers: u32) -> Self {
+        Self {
+            voting_powers: vec![BASE_VOTING_POWER; peers as usize],
+            validator_changes: vec![],
+            seed: 0,
+            context: BaseContext::new(),
+            consensus: ConsensusParams::default(),
+            timeouts: TimeoutDurations::default(),
+            mock_signatures: false,
+            faults: LinkFaults::default(),
+            links: vec![],
+            partitions: None,
+            byzantine: vec![],
+            workload: Workload::default(),
+            stall_limits: StallLimits::default(),
+            record_trace: false,
+            observers: vec![],
+        }
+    }
+
+    /// One peer for each of the given voting powers, in that order,
+    /// instead of the peers given to [`SimulatorBuilder::new`].
+    /// Building panics if every voting power is zero.
+    pub fn voting_powers(mut self, voting_powers: Vec<VotingPower>) -> Self {
+        self.voting_powers = voting_powers;
+        self
+    }
+
+    /// See [`Simulator::change_validators`].
+    pub fn validators_from(
+        mut self,
+        from: BaseHeight,
+        validators: Vec<(BasePeerAddress, VotingPower)>,
+    ) -> Self {
+        self.validator_changes.push((from, validators));
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// The context which every peer has a copy of, e.g., to select proposers.
+    pub fn context(mut self, context: BaseContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// See [`Simulator::set_consensus_params`].
+    pub fn consensus_params(mut self, consensus: ConsensusParams) -> Self {
+        self.consensus = consensus;
+        self
+    }
+
+    pub fn timeouts(mut self, timeouts: TimeoutDurations) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// See [`Simulator::set_mock_signature_verification`].
+    pub fn mock_signatures(mut self, mock: bool) -> Self {
+        self.mock_signatures = mock;
+        self
+    }
+
+    /// The faults of every link which has no faults of its own.
+    pub fn faults(mut self, faults: LinkFaults) -> Self {
+        self.faults = faults;
+        self
+    }
+
+    /// The faults of the link from `source` to `destination`.
+    pub fn link(
+        mut self,
+        source: BasePeerAddress,
+        destination: BasePeerAddress,
+        faults: LinkFaults,
+    ) -> Self {
+        self.links.push((source, destination, faults));
+        self
+    }
+
+    pub fn partitions(mut self, schedule: PartitionSchedule) -> Self {
+        self.partitions = Some(schedule);
+        self
+    }
+
+    pub fn byzantine(mut self, peer: BasePeerAddress, behaviour: ByzantineBehaviour) -> Self {
+        self.byzantine.push((peer, behaviour));
+        self
+    }
+
+    pub fn workload(mut self, workload: Workload) -> Self {
+        self.workload = workload;
+        self
+    }
+
+    pub fn stall_limits(mut self, limits: StallLimits) -> Self {
+        self.stall_limits = limits;
+        self
+    }
+
+    /// See [`Simulator::record_trace`].
+    pub fn record_trace(mut self) -> Self {
+        self.record_trace = true;
+        self
+    }
+
+    /// Adds an observer, which the simulator notifies in the order they were added.
+    pub fn observer(mut self, observer: impl Observer + 'static) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    pub fn build(self) -> (Simulator, States, ProposalsSender, DecisionsReceiver) {
+        let (mut n, mut states, ps, dr) =
+            Simulator::with_voting_powers(self.context, self.seed, self.voting_powers);
+
+        n.set_consensus_params(&mut states, self.consensus);
+
+        n.set_timeout_durations(self.timeouts);
+        n.set_mock_signature_verification(self.mock_signatures);
+        for (from, validators) in self.validator_changes {
+            n.change_validators(from, &validators);
+        }
+
+        let mut network = NetworkModel::new(self.faults, self.seed);
+        for (source, destination, faults) in self.links {
+            network.set_link(source, destination, faults);
+        }
+        n.set_network_model(network);
+
+        if let Some(schedule) = self.partitions {
+            n.set_partition_schedule(schedule);
+        }
+        for (peer, behaviour) in self.byzantine {
+            n.set_byzantine(peer, behaviour);
+        }
+        n.set_workload(self.workload);
+        n.set_stall_limits(self.stall_limits);
+
+        if self.record_trace {
+            n.record_trace();
+        }
+        for observer in self.observers {
+            n.add_observer(observer);
+        }
+
+        (n, states, ps, dr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    use crate::builder::SimulatorBuilder;
+    use crate::byzantine::ByzantineBehaviour;
+    use crate::context::address::BasePeerAddress;
+    use crate::context::proposer::ProposerSelection;
+    use crate::context::BaseContext;
+    use crate::decision::Decision;
+    use crate::invariants::ViolationReport;
+    use crate::observer::Observer;
+    use crate::report::{StopConditions, StopReason};
+    use crate::simulator::Workload;
+
+    #[derive(Default)]
+    struct Collect {
+        decisions: Vec<Decision>,
+        violations: Vec<ViolationReport>,
+    }
+
+    impl Observer for Rc<RefCell<Collect>> {
+        fn on_decision(&mut self, decision: &Decision) {
+            self.borrow_mut().decisions.push(decision.clone());
+        }
+
+        fn on_violation(&mut self, report: &ViolationReport) {
+            self.borrow_mut().violations.push(report.clone());
+        }
+    }
+
+    #[test]
+    fn observers_see_what_the_report_says() {
+        let collected = Rc::new(RefCell::new(Collect::default()));
+
+        // Two equivocating peers out of four break safety
+        let (mut n, mut states, _, _) = SimulatorBuilder::new(4)
+            .seed(5)
+            .context(BaseContext::with_proposer_selection(
+                ProposerSelection::RoundRobin,
+            ))
+            .byzantine(BasePeerAddress(0), ByzantineBehaviour::equivocating())
+            .byzantine(BasePeerAddress(1), ByzantineBehaviour::equivocating())
+            .workload(Workload::Sequential(45))
+            .observer(collected.clone())
+            .build();
+
+        let stop = StopConditions {
+            violation: true,
+            time: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        let report = n.run(&mut states, stop);
+        assert_eq!(report.reason, StopReason::Violation);
+
+        let collected = collected.borrow();
+        assert_eq!(collected.decisions, report.decisions);
+        assert_eq!(collected.violations, report.violations);
+    }
+}
@@ -0,0 +1,135 @@
+/// Byzantine behaviours, which make a peer deviate from the protocol
+/// in the messages it sends to other peers.
+///
+use malachite_core_consensus::{ProposedValue, SignedConsensusMsg};
+use malachite_core_types::{NilOrVal, SigningProvider, VoteType};
+
+use crate::context::address::BasePeerAddress;
+use crate::context::signing_provider::BaseSigningProvider;
+use crate::context::value::{BaseValue, BaseValueId};
+use crate::context::BaseContext;
+
+// Two variants of the same value differ in this bit only.
+const VARIANT_BIT: u64 = 1 << 63;
+
+/// The value which a peer voting for values it never received votes for.
+pub const UNKNOWN_VALUE: BaseValueId = BaseValueId(u64::MAX >> 1);
+
+/// How a peer deviates from the protocol. By default, it does not.
+///
+/// A Byzantine peer runs consensus honestly, but tampers with the
+/// messages it publishes before sending them. It sends conflicting messages
+/// to two subsets of the other peers, the even and the odd peers by index,
+/// each subset receiving its own variant of a value.
+/// All Byzantine peers send the same variant to the same subset, so that
+/// they collude to make the subsets decide different values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ByzantineBehaviour {
+    /// When proposing a value, send a proposal for a variant of that
+    /// value to each subset.
+    pub equivocate_proposals: bool,
+
+    /// When prevoting for a value, send a prevote for a variant of that
+    /// value to each subset.
+    pub double_prevote: bool,
+
+    /// When precommitting for a value, send a precommit for a variant of that
+    /// value to each subset.
+    pub double_precommit: bool,
+
+    /// Vote for [`UNKNOWN_VALUE`], which no peer ever proposed, instead of the
+    /// value or nil it would vote for.
+    pub vote_for_unknown_values: bool,
+}
+
+impl ByzantineBehaviour {
+    /// Equivocates on proposals, prevotes and precommits.
+    pub fn equivocating() -> Self {
+        Self {
+            equivocate_proposals: true,
+            double_prevote: true,
+            double_precommit: true,
+            vote_for_unknown_values: false,
+        }
+    }
+
+    pub fn is_honest(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The messages to send instead of `msg`, to the even peers and to the
+    /// odd peers respectively, signed again with `signing_provider`
+    /// wherever they differ from `msg`.
+    pub fn tamper(
+        &self,
+        msg: &SignedConsensusMsg<BaseContext>,
+        signing_provider: &BaseSigningProvider,
+    ) -> [SignedConsensusMsg<BaseContext>; 2] {
+        match msg {
+            SignedConsensusMsg::Proposal(sp) if self.equivocate_proposals => {
+                [false, true].map(|odd| {
+                    let mut proposal = sp.message.clone();
+                    proposal.value = BaseValue(variant(proposal.value.0, odd));
+
+                    SignedConsensusMsg::Proposal(signing_provider.sign_proposal(proposal))
+                })
+            }
+            SignedConsensusMsg::Vote(sv) if self.tampers_with(sv.vote_type) => {
+                let double = match sv.vote_type {
+                    VoteType::Prevote => self.double_prevote,
+                    VoteType::Precommit => self.double_precommit,
+                };
+
+                [false, true].map(|odd| {
+                    let mut vote = sv.message.clone();
+                    if self.vote_for_unknown_values {
+                        vote.value_id = NilOrVal::Val(UNKNOWN_VALUE);
+                    }
+                    if double {
+                        vote.value_id = vote.value_id.map(|id| BaseValueId(variant(id.0, odd)));
+                    }
+
+                    SignedConsensusMsg::Vote(signing_provider.sign_vote(vote))
+                })
+            }
+            _ => [msg.clone(), msg.clone()],
+        }
+    }
+
+    /// The values to stream instead of `value`, to the even peers and to the
+    /// odd peers respectively, so that they match the tampered proposals.
+    pub fn tamper_value(
+        &self,
+        value: &ProposedValue<BaseContext>,
+    ) -> [ProposedValue<BaseContext>; 2] {
+        [false, true].map(|odd| {
+            let mut value = value.clone();
+            if self.equivocate_proposals {
+                value.value = BaseValue(variant(value.value.0, odd));
+            }
+            value
+        })
+    }
+
+    fn tampers_with(&self, vote_type: VoteType) -> bool {
+        self.vote_for_unknown_values
+            || match vote_type {
+                VoteType::Prevote => self.double_prevote,
+                VoteType::Precommit => self.double_precommit,
+            }
+    }
+}
+
+/// Whether `peer` belongs to the odd subset of peers, see [`ByzantineBehaviour`].
+pub fn is_odd(peer: &BasePeerAddress) -> bool {
+    peer.0 % 2 == 1
+}
+
+// The variant of `value` for the even or the odd peers.
+fn variant(value: u64, odd: bool) -> u64 {
+    if odd {
+        value | VARIANT_BIT
+    } else {
+        value & !VARIANT_BIT
+    }
+}
@@ -0,0 +1,301 @@
+/// The command-line interface of the simulator binary: each subcommand
+/// maps onto a [`Scenario`] or a [`Trace`], and through them onto the
+/// [`Simulator`] API.
+///
+use std::fmt;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use tracing::level_filters::LevelFilter;
+
+use malachite_simulator::report::{CancelHandle, SimulationReport, StopConditions};
+use malachite_simulator::scenario::{Scenario, ScenarioError};
+use malachite_simulator::simulator::Simulator;
+use malachite_simulator::trace::{Trace, TraceError};
+
+#[derive(Debug, Parser)]
+#[command(about = "Simulates a network of peers running Malachite consensus")]
+pub struct Cli {
+    /// How to format the logs, which go to the standard output.
+    #[arg(long, value_enum, default_value_t, global = true)]
+    pub log_format: LogFormat,
+
+    /// The level of the logs of the simulator, e.g., `trace` to follow every
+    /// step. `RUST_LOG`, if set, overrides it.
+    #[arg(long, default_value = "info", global = true)]
+    pub log_level: LevelFilter,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum LogFormat {
+    Full,
+    #[default]
+    Compact,
+    Pretty,
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Runs a scenario to completion, then sums it up.
+    /// Without a scenario file, four peers decide until consensus stalls.
+    Run {
+        scenario: Option<PathBuf>,
+
+        #[command(flatten)]
+        overrides: Overrides,
+
+        /// Records the trace of the run to this file.
+        #[arg(long)]
+        trace: Option<PathBuf>,
+    },
+
+    /// Re-drives the peers from a trace, then sums up their decisions.
+    Replay { trace: PathBuf },
+
+    /// Checks the decisions in a trace for safety, without executing consensus.
+    Check { trace: PathBuf },
+
+    /// Runs a scenario once for each combination of peer count and seed,
+    /// then sums up each run on a line.
+    Sweep {
+        scenario: Option<PathBuf>,
+
+        /// Peer counts, e.g., 4,7,10.
+        #[arg(long, value_delimiter = ',', default_value = "4")]
+        peers: Vec<u32>,
+
+        /// Seeds, either listed, e.g., 1,5,8, or as a range, e.g., 0..20.
+        #[arg(long, default_value = "0")]
+        seeds: Seeds,
+
+        /// Stops each run once every correct peer decided this many heights.
+        #[arg(long, default_value_t = 10)]
+        heights: u64,
+    },
+}
+
+/// Flags which override what the scenario file says.
+#[derive(Debug, Args)]
+pub struct Overrides {
+    #[arg(long)]
+    pub peers: Option<u32>,
+
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Stops once every correct peer decided this many heights.
+    #[arg(long)]
+    pub heights: Option<u64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Seeds(pub Vec<u64>);
+
+impl FromStr for Seeds {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| n.trim().parse::<u64>().map_err(|e| format!("{n}: {e}"));
+
+        match s.split_once("..") {
+            Some((start, end)) => Ok(Seeds((parse(start)?..parse(end)?).collect())),
+            None => s.split(',').map(parse).collect::<Result<_, _>>().map(Seeds),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    Scenario(ScenarioError),
+    Trace(TraceError),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Scenario(e) => write!(f, "{e}"),
+            CliError::Trace(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl From<ScenarioError> for CliError {
+    fn from(e: ScenarioError) -> Self {
+        CliError::Scenario(e)
+    }
+}
+
+impl From<TraceError> for CliError {
+    fn from(e: TraceError) -> Self {
+        CliError::Trace(e)
+    }
+}
+
+impl Command {
+    /// Executes the command, printing what happened, until done or `cancel`led.
+    /// Fails if safety is violated, or if consensus stalls.
+    pub fn execute(self, cancel: &CancelHandle) -> Result<ExitCode, CliError> {
+        match self {
+            Command::Run {
+                scenario,
+                overrides,
+                trace,
+            } => {
+                let mut scenario = load(scenario)?;
+                overrides.apply(&mut scenario);
+                let (mut n, mut states) = scenario.build()?;
+                if trace.is_some() {
+                    n.record_trace();
+                }
+                let stop = StopConditions {
+                    cancel: Some(cancel.clone()),
+                    ..scenario.stop_conditions()
+                };
+                let report = n.run(&mut states, stop);
+                if let (Some(path), Some(t)) = (trace, n.trace()) {
+                    t.save(path)?;
+                }
+
+                print!("{report}");
+                Ok(exit_code(&report))
+            }
+
+            Command::Replay { trace } => {
+                let (mut n, mut states, _) = Simulator::replay(&Trace::load(trace)?);
+                let stop = StopConditions {
+                    cancel: Some(cancel.clone()),
+                    ..Default::default()
+                };
+                let report = n.run(&mut states, stop);
+
+                print!("{report}");
+                Ok(exit_code(&report))
+            }
+
+            Command::Check { trace } => {
+                let violations = Trace::load(trace)?.check();
+                for report in violations.iter() {
+                    println!(
+                        "at step {} ({:?}): {}",
+                        report.step, report.time, report.violation
+                    );
+                }
+
+                if violations.is_empty() {
+                    println!("no safety violation");
+                    Ok(ExitCode::SUCCESS)
+                } else {
+                    Ok(ExitCode::FAILURE)
+                }
+            }
+
+            Command::Sweep {
+                scenario,
+                peers,
+                seeds,
+                heights,
+            } => {
+                let base = load(scenario)?;
+                let mut failed = false;
+
+                for p in peers.iter() {
+                    for seed in seeds.0.iter().take_while(|_| !cancel.is_cancelled()) {
+                        let mut scenario = base.clone();
+                        scenario.peers = *p;
+                        scenario.seed = *seed;
+                        scenario.stop.heights = Some(heights);
+                        let (mut n, mut states) = scenario.build()?;
+                        let stop = StopConditions {
+                            cancel: Some(cancel.clone()),
+                            ..scenario.stop_conditions()
+                        };
+                        let report = n.run(&mut states, stop);
+                        println!(
+                            "peers {p} seed {seed}: {} after {} steps ({:?}), {} decisions, {} violations",
+                            report.reason,
+                            report.steps,
+                            report.time,
+                            report.decisions.len(),
+                            report.violations.len(),
+                        );
+                        failed |= !report.is_success();
+                    }
+                }
+
+                Ok(if failed {
+                    ExitCode::FAILURE
+                } else {
+                    ExitCode::SUCCESS
+                })
+            }
+        }
+    }
+}
+
+impl Overrides {
+    fn apply(&self, scenario: &mut Scenario) {
+        if let Some(peers) = self.peers {
+            scenario.peers = peers;
+        }
+        if let Some(seed) = self.seed {
+            scenario.seed = seed;
+        }
+        if let Some(heights) = self.heights {
+            scenario.stop.heights = Some(heights);
+        }
+    }
+}
+
+// Loads the scenario at `path`, if any, or else the default one.
+fn load(path: Option<PathBuf>) -> Result<Scenario, ScenarioError> {
+    match path {
+        Some(path) => Scenario::load(path),
+        None => Ok(Scenario::default()),
+    }
+}
+
+fn exit_code(report: &SimulationReport) -> ExitCode {
+    if report.is_success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use tracing::level_filters::LevelFilter;
+
+    use crate::cli::{Cli, Command};
+
+    #[test]
+    fn flags_map_onto_the_scenario() {
+        let cli =
+            Cli::try_parse_from(["sim", "sweep", "--peers", "4,7", "--seeds", "3..6"]).unwrap();
+        let Command::Sweep { peers, seeds, .. } = cli.command else {
+            panic!("not a sweep");
+        };
+        assert_eq!(peers, vec![4, 7]);
+        assert_eq!(seeds.0, vec![3, 4, 5]);
+        assert_eq!(cli.log_level, LevelFilter::INFO);
+
+        let cli = Cli::try_parse_from(["sim", "--log-level", "trace", "check", "t"]).unwrap();
+        assert_eq!(cli.log_level, LevelFilter::TRACE);
+
+        let cli = Cli::try_parse_from(["sim", "run", "--peers", "5", "--heights", "2"]).unwrap();
+        let Command::Run { overrides, .. } = cli.command else {
+            panic!("not a run");
+        };
+        let mut scenario = Default::default();
+        overrides.apply(&mut scenario);
+        assert_eq!(scenario.peers, 5);
+        assert_eq!(scenario.stop.heights, Some(2));
+    }
+}
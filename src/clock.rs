@@ -0,0 +1,129 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::time::Duration;
+
+use crate::context::address::BasePeerAddress;
+use crate::simulator::{Change, Envelope};
+
+/// A virtual clock, measuring the simulated time elapsed since the simulation started.
+///
+/// The clock does not follow the wall clock. Instead, the simulator
+/// moves it forward to the moment of each [`Event`] it handles,
+/// so simulating long periods of time takes no longer than the
+/// handling of the events within that period.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VirtualClock {
+    now: Duration,
+}
+
+impl VirtualClock {
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// Moves the clock forward to `time`. The clock never goes back.
+    pub fn advance_to(&mut self, time: Duration) {
+        assert!(time >= self.now, "the virtual clock cannot go back in time");
+
+        self.now = time;
+    }
+}
+
+/// Something that happens at a given moment in simulated time.
+pub enum Event {
+    /// An envelope reaches its destination.
+    Deliver(Box<Envelope>),
+
+    /// Some timeout may elapse at the given peer.
+    Wake(BasePeerAddress),
+
+    /// The simulated environment changes.
+    Change(Change),
+}
+
+// An event together with the moment it happens at.
+// The sequence number breaks ties, so that events happening at the same moment
+// keep the order in which they were pushed.
+struct Scheduled {
+    at: Duration,
+    seq: u64,
+    event: Event,
+}
+
+impl PartialEq for Scheduled {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Scheduled {}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scheduled {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.at, self.seq).cmp(&(other.at, other.seq))
+    }
+}
+
+/// A priority queue of [`Event`]s keyed by simulated time.
+#[derive(Default)]
+pub struct EventQueue {
+    heap: BinaryHeap<Reverse<Scheduled>>,
+    next_seq: u64,
+}
+
+impl EventQueue {
+    pub fn push(&mut self, at: Duration, event: Event) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        self.heap.push(Reverse(Scheduled { at, seq, event }));
+    }
+
+    /// Removes the earliest event, together with the moment it happens at.
+    pub fn pop(&mut self) -> Option<(Duration, Event)> {
+        self.heap.pop().map(|Reverse(s)| (s.at, s.event))
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Keeps only the events for which `keep` holds.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Event) -> bool) {
+        self.heap.retain(|Reverse(s)| keep(&s.event));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::clock::{Event, EventQueue};
+    use crate::context::address::BasePeerAddress;
+
+    #[test]
+    fn events_pop_in_time_then_push_order() {
+        let mut queue = EventQueue::default();
+
+        queue.push(Duration::from_millis(200), Event::Wake(BasePeerAddress(0)));
+        queue.push(Duration::from_millis(100), Event::Wake(BasePeerAddress(1)));
+        queue.push(Duration::from_millis(100), Event::Wake(BasePeerAddress(2)));
+
+        let mut popped = vec![];
+        while let Some((at, Event::Wake(peer))) = queue.pop() {
+            popped.push((at.as_millis(), peer.0));
+        }
+
+        assert_eq!(popped, vec![(100, 1), (100, 2), (200, 0)]);
+    }
+}
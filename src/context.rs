@@ -5,41 +5,65 @@ use height::BaseHeight;
 use peer::BasePeer;
 use peer_set::BasePeerSet;
 use proposals::{BaseProposal, BaseProposalPart};
+use proposer::ProposerSelection;
 use signing_provider::BaseSigningProvider;
-use signing_scheme::PublicKey;
 use value::BaseValue;
 use vote::BaseVote;
 
 // Type definitions needed for the context
 pub mod address;
+pub mod encoding;
 pub mod height;
 pub mod peer;
 pub mod peer_set;
 pub mod proposals;
+pub mod proposer;
 pub mod signing_provider;
 pub mod signing_scheme;
 pub mod value;
 pub mod vote;
 
-/// The context is shared across all the peers in the simulated network.
-/// The signing provider within the context is also shared across all peers.
-/// This means all peers have the same signing key: big assumption, but
-/// inconsequential for the simulated environment here.
+/// Each peer in the simulated network has its own context.
+/// The contexts of all peers are identical, except for the signing provider,
+/// which holds the private key of the peer owning the context.
 #[derive(Clone)]
 pub struct BaseContext {
-    pub signing_provider: BaseSigningProvider,
+    /// The signing provider of the peer owning the context, if any.
+    /// A context without one panics when asked for it, rather than signing
+    /// on behalf of some other peer.
+    pub signing_provider: Option<BaseSigningProvider>,
+
+    /// How to select the proposer of each height and round.
+    pub proposer_selection: ProposerSelection,
+}
+
+impl Default for BaseContext {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BaseContext {
     pub fn new() -> BaseContext {
+        Self::with_proposer_selection(ProposerSelection::default())
+    }
+
+    /// The context selecting proposers as per `proposer_selection`. It cannot
+    /// sign until [`BaseContext::with_signing_provider`] gives it the key of
+    /// its own peer.
+    pub fn with_proposer_selection(proposer_selection: ProposerSelection) -> BaseContext {
         BaseContext {
-            signing_provider: BaseSigningProvider::new(),
+            signing_provider: None,
+            proposer_selection,
         }
     }
 
-    // Get the public key that all peers share.
-    pub fn shared_public_key(&self) -> PublicKey {
-        self.signing_provider.public_key()
+    /// A copy of this context, for the peer owning `signing_provider`.
+    pub fn with_signing_provider(&self, signing_provider: BaseSigningProvider) -> BaseContext {
+        BaseContext {
+            signing_provider: Some(signing_provider),
+            ..self.clone()
+        }
     }
 }
 
@@ -58,25 +82,23 @@ impl Context for BaseContext {
     fn select_proposer<'a>(
         &self,
         validator_set: &'a Self::ValidatorSet,
-        _height: Self::Height,
-        _round: Round,
+        height: Self::Height,
+        round: Round,
     ) -> &'a Self::Validator {
-        // Keep it simple, the proposer is always the same peer
-        validator_set
-            .peers
-            .first()
-            .expect("no peer found in the validator set")
+        self.proposer_selection.select(validator_set, height, round)
     }
 
     fn signing_provider(&self) -> &Self::SigningProvider {
-        &self.signing_provider
+        self.signing_provider
+            .as_ref()
+            .expect("the context has no signing provider")
     }
 
     fn new_proposal(
         height: Self::Height,
         round: Round,
         value: Self::Value,
-        _pol_round: Round,
+        pol_round: Round,
         address: Self::Address,
     ) -> Self::Proposal {
         BaseProposal {
@@ -84,6 +106,7 @@ impl Context for BaseContext {
             value,
             proposer: address,
             round,
+            pol_round,
         }
     }
 
@@ -0,0 +1,296 @@
+/// Canonical byte encoding of the messages which peers sign.
+///
+/// Every encoded message starts with a header of two bytes: the version of the
+/// encoding and a tag identifying the kind of message. Integers are big-endian
+/// and every field of the message is encoded, so that two different messages
+/// never have the same encoding.
+///
+use std::fmt;
+
+use bytes::{Buf, BufMut};
+use malachite_core_types::Round;
+
+use crate::context::signing_scheme::Signature;
+
+/// The version of the encoding. Decoding rejects any other version.
+pub const ENCODING_VERSION: u8 = 1;
+
+/// Identifies the kind of message encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MessageTag {
+    Prevote = 1,
+    Precommit = 2,
+    Proposal = 3,
+    ProposalPart = 4,
+}
+
+impl TryFrom<u8> for MessageTag {
+    type Error = DecodingError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(MessageTag::Prevote),
+            2 => Ok(MessageTag::Precommit),
+            3 => Ok(MessageTag::Proposal),
+            4 => Ok(MessageTag::ProposalPart),
+            _ => Err(DecodingError::UnknownTag(value)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodingError {
+    UnsupportedVersion(u8),
+    UnknownTag(u8),
+    UnexpectedTag(MessageTag),
+    UnexpectedEnd,
+    InvalidRound(i64),
+    InvalidFlag(u8),
+    UnknownPartKind(u8),
+    InvalidSignature,
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodingError::UnsupportedVersion(v) => write!(f, "unsupported version {v}"),
+            DecodingError::UnknownTag(t) => write!(f, "unknown message tag {t}"),
+            DecodingError::UnexpectedTag(t) => write!(f, "unexpected message tag {t:?}"),
+            DecodingError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            DecodingError::InvalidRound(r) => write!(f, "invalid round {r}"),
+            DecodingError::InvalidFlag(b) => write!(f, "invalid flag {b}"),
+            DecodingError::UnknownPartKind(k) => write!(f, "unknown part kind {k}"),
+            DecodingError::InvalidSignature => write!(f, "invalid signature"),
+            DecodingError::TrailingBytes(n) => write!(f, "{n} trailing bytes"),
+        }
+    }
+}
+
+/// Starts the encoding of a message of kind `tag`.
+pub fn encode_header(tag: MessageTag) -> Vec<u8> {
+    vec![ENCODING_VERSION, tag as u8]
+}
+
+/// Rounds are encoded as an `i64`, where [`Round::Nil`] is -1.
+pub fn put_round(buf: &mut Vec<u8>, round: Round) {
+    buf.put_i64(round.as_i64());
+}
+
+/// Optional fields are encoded with a flag byte: 0 if absent, 1 if present.
+pub fn put_flag(buf: &mut Vec<u8>, present: bool) {
+    buf.put_u8(present as u8);
+}
+
+/// Reads the fields of an encoded message in order.
+pub struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Checks the header of `bytes`, which must encode a message of one of the
+    /// kinds in `expected`, and returns the tag of the message with a reader
+    /// positioned on its first field.
+    pub fn new(
+        bytes: &'a [u8],
+        expected: &[MessageTag],
+    ) -> Result<(MessageTag, Self), DecodingError> {
+        let mut reader = Self { buf: bytes };
+
+        let version = reader.u8()?;
+        if version != ENCODING_VERSION {
+            return Err(DecodingError::UnsupportedVersion(version));
+        }
+
+        let tag = MessageTag::try_from(reader.u8()?)?;
+        if !expected.contains(&tag) {
+            return Err(DecodingError::UnexpectedTag(tag));
+        }
+
+        Ok((tag, reader))
+    }
+
+    fn ensure(&self, len: usize) -> Result<(), DecodingError> {
+        if self.buf.remaining() < len {
+            return Err(DecodingError::UnexpectedEnd);
+        }
+        Ok(())
+    }
+
+    pub fn u8(&mut self) -> Result<u8, DecodingError> {
+        self.ensure(1)?;
+        Ok(self.buf.get_u8())
+    }
+
+    pub fn u32(&mut self) -> Result<u32, DecodingError> {
+        self.ensure(4)?;
+        Ok(self.buf.get_u32())
+    }
+
+    pub fn u64(&mut self) -> Result<u64, DecodingError> {
+        self.ensure(8)?;
+        Ok(self.buf.get_u64())
+    }
+
+    pub fn round(&mut self) -> Result<Round, DecodingError> {
+        self.ensure(8)?;
+        match self.buf.get_i64() {
+            -1 => Ok(Round::Nil),
+            r if r >= 0 && r <= u32::MAX as i64 => Ok(Round::new(r as u32)),
+            r => Err(DecodingError::InvalidRound(r)),
+        }
+    }
+
+    pub fn flag(&mut self) -> Result<bool, DecodingError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(DecodingError::InvalidFlag(b)),
+        }
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], DecodingError> {
+        self.ensure(len)?;
+        let (bytes, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    pub fn signature(&mut self) -> Result<Signature, DecodingError> {
+        Signature::try_from(self.bytes(64)?).map_err(|_| DecodingError::InvalidSignature)
+    }
+
+    /// Completes the decoding, which must have consumed every byte.
+    pub fn finish(self) -> Result<(), DecodingError> {
+        match self.buf.remaining() {
+            0 => Ok(()),
+            n => Err(DecodingError::TrailingBytes(n)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use malachite_core_types::{Extension, NilOrVal, Round, SigningProvider, VoteType};
+
+    use crate::context::address::BasePeerAddress;
+    use crate::context::height::BaseHeight;
+    use crate::context::proposals::{BaseProposal, BaseProposalPart, PartContent};
+    use crate::context::signing_provider::BaseSigningProvider;
+    use crate::context::value::{BaseValue, BaseValueId};
+    use crate::context::vote::BaseVote;
+
+    fn vote() -> BaseVote {
+        BaseVote {
+            vote_type: VoteType::Precommit,
+            height: BaseHeight(3),
+            value_id: NilOrVal::Val(BaseValueId(45)),
+            round: Round::new(1),
+            voter: BasePeerAddress(2),
+            extension: None,
+        }
+    }
+
+    fn proposal() -> BaseProposal {
+        BaseProposal {
+            height: BaseHeight(3),
+            value: BaseValue(45),
+            proposer: BasePeerAddress(1),
+            round: Round::new(2),
+            pol_round: Round::new(1),
+        }
+    }
+
+    #[test]
+    fn encoding_round_trips() {
+        let extension = BaseSigningProvider::derive(0, 2)
+            .sign_vote(vote())
+            .map(|_| Extension::new(Bytes::from_static(b"ext")));
+        let extended = BaseVote {
+            extension: Some(extension),
+            ..vote()
+        };
+
+        for v in [vote(), extended] {
+            assert_eq!(BaseVote::from_bytes(&v.to_bytes()), Ok(v));
+        }
+        assert_eq!(
+            BaseProposal::from_bytes(&proposal().to_bytes()),
+            Ok(proposal())
+        );
+
+        for content in [
+            PartContent::Init {
+                valid_round: Round::Nil,
+            },
+            PartContent::Data(vec![1, 2, 3]),
+            PartContent::Fin,
+        ] {
+            let part = BaseProposalPart {
+                height: BaseHeight(3),
+                round: Round::new(2),
+                proposer: BasePeerAddress(1),
+                sequence: 4,
+                content,
+            };
+            assert_eq!(BaseProposalPart::from_bytes(&part.to_bytes()), Ok(part));
+        }
+
+        // A vote does not decode as a proposal, nor the other way around
+        assert!(BaseProposal::from_bytes(&vote().to_bytes()).is_err());
+        assert!(BaseVote::from_bytes(&proposal().to_bytes()).is_err());
+    }
+
+    #[test]
+    fn distinct_messages_have_distinct_sign_bytes() {
+        let votes = [
+            vote(),
+            BaseVote {
+                vote_type: VoteType::Prevote,
+                ..vote()
+            },
+            BaseVote {
+                height: BaseHeight(4),
+                ..vote()
+            },
+            BaseVote {
+                round: Round::new(2),
+                ..vote()
+            },
+            BaseVote {
+                value_id: NilOrVal::Nil,
+                ..vote()
+            },
+            BaseVote {
+                voter: BasePeerAddress(3),
+                ..vote()
+            },
+        ];
+        let proposals = [
+            proposal(),
+            BaseProposal {
+                pol_round: Round::Nil,
+                ..proposal()
+            },
+            BaseProposal {
+                proposer: BasePeerAddress(2),
+                ..proposal()
+            },
+            BaseProposal {
+                value: BaseValue(46),
+                ..proposal()
+            },
+        ];
+
+        let mut encodings: Vec<Vec<u8>> = votes.iter().map(|v| v.to_bytes()).collect();
+        encodings.extend(proposals.iter().map(|p| p.to_bytes()));
+
+        for (i, a) in encodings.iter().enumerate() {
+            for b in encodings.iter().skip(i + 1) {
+                assert_ne!(a, b);
+            }
+        }
+    }
+}
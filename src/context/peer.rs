@@ -6,25 +6,35 @@ use tracing::debug;
 use crate::context::address::BasePeerAddress;
 use crate::context::BaseContext;
 
-/// This is the voting power of each peer.
+/// This is the voting power of each peer, unless given another one.
 pub const BASE_VOTING_POWER: u64 = 1;
 
 /// The most basic definition of a peer.
-/// All peers have equal voting power, [`BASE_VOTING_POWER`].
 /// Implements [`Validator`] trait.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct BasePeer {
     pub id: BasePeerAddress,
     pub public_key: PublicKey<BaseContext>,
+    pub voting_power: VotingPower,
 }
 
 impl BasePeer {
+    /// A peer with the voting power [`BASE_VOTING_POWER`].
     pub fn new(id: u32, public_key: PublicKey<BaseContext>) -> BasePeer {
-        debug!(public_key = ?public_key, "created new peer");
+        Self::with_voting_power(id, public_key, BASE_VOTING_POWER)
+    }
+
+    pub fn with_voting_power(
+        id: u32,
+        public_key: PublicKey<BaseContext>,
+        voting_power: VotingPower,
+    ) -> BasePeer {
+        debug!(public_key = ?public_key, voting_power, "created new peer");
 
         BasePeer {
             id: BasePeerAddress::new(id),
             public_key,
+            voting_power,
         }
     }
 }
@@ -45,6 +55,6 @@ impl Validator<BaseContext> for BasePeer {
     }
 
     fn voting_power(&self) -> VotingPower {
-        BASE_VOTING_POWER
+        self.voting_power
     }
 }
@@ -1,9 +1,12 @@
 /// Implementation of `PeerSet` and some utility methods.
 ///
 use std::cmp::PartialEq;
+use std::collections::HashMap;
+use std::sync::Arc;
 use tracing::warn;
 
-use crate::context::peer::BasePeer;
+use crate::context::peer::{BasePeer, BASE_VOTING_POWER};
+use crate::context::proposer::weighted_rotation;
 use crate::context::BaseContext;
 use crate::context::{address::BasePeerAddress, signing_scheme::PublicKey};
 
@@ -13,28 +16,73 @@ use malachite_core_types::{ValidatorSet, VotingPower};
 /// Implements [`ValidatorSet`].
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct BasePeerSet {
-    pub peers: Vec<BasePeer>,
+    peers: Vec<BasePeer>,
+    // The position of each peer in `peers`, by address
+    index: HashMap<BasePeerAddress, usize>,
+    // The proposer of each turn of weighted proposer selection, over one cycle
+    proposer_rotation: Arc<[usize]>,
 }
 
 impl BasePeerSet {
-    /// Create a new set of peers of cardinality `size`.
-    pub fn new(size: u32, public_key: PublicKey) -> Self {
+    /// Create a new set of peers, one for each public key.
+    /// The peer at index `i` owns the public key at index `i`.
+    /// Every peer has the same voting power, [`BASE_VOTING_POWER`].
+    pub fn new(public_keys: Vec<PublicKey>) -> Self {
+        let voting_powers = vec![BASE_VOTING_POWER; public_keys.len()];
+
+        Self::with_voting_powers(public_keys, voting_powers)
+    }
+
+    /// Create a new set of peers, one for each public key, where the peer
+    /// at index `i` owns the public key and the voting power at index `i`.
+    ///
+    /// Panics if the peers have no voting power at all.
+    pub fn with_voting_powers(
+        public_keys: Vec<PublicKey>,
+        voting_powers: Vec<VotingPower>,
+    ) -> Self {
+        assert_eq!(public_keys.len(), voting_powers.len());
         let mut peers = vec![];
 
-        for i in 0..size {
-            let peer = BasePeer::new(i, public_key);
-            warn!(peer = %i, "created");
+        for (i, (public_key, voting_power)) in
+            public_keys.into_iter().zip(voting_powers).enumerate()
+        {
+            let peer = BasePeer::with_voting_power(i as u32, public_key, voting_power);
+            warn!(peer = %i, voting_power, "created");
 
             peers.push(peer);
         }
 
         peers.into()
     }
+
+    /// The peers, in the order the set was created with.
+    pub fn peers(&self) -> &[BasePeer] {
+        &self.peers
+    }
+
+    /// The index of the proposer of each turn of weighted proposer
+    /// selection, over one cycle, see [`weighted_rotation`].
+    pub fn proposer_rotation(&self) -> &[usize] {
+        &self.proposer_rotation
+    }
 }
 
 impl From<Vec<BasePeer>> for BasePeerSet {
     fn from(value: Vec<BasePeer>) -> Self {
-        Self { peers: value }
+        // No proposer could be selected among peers without voting power
+        assert!(
+            value.iter().any(|p| p.voting_power > 0),
+            "no voting power in the peer set"
+        );
+        let index = value.iter().enumerate().map(|(i, p)| (p.id, i)).collect();
+        let proposer_rotation = weighted_rotation(&value).into();
+
+        Self {
+            peers: value,
+            index,
+            proposer_rotation,
+        }
     }
 }
 
@@ -45,12 +93,11 @@ impl ValidatorSet<BaseContext> for BasePeerSet {
 
     // Note: VotingPower is a primitive we can simply re-use
     fn total_voting_power(&self) -> VotingPower {
-        // Todo: Double-check if this is fishy
-        self.count() as u64
+        self.peers.iter().map(|p| p.voting_power).sum()
     }
 
     fn get_by_address(&self, address: &BasePeerAddress) -> Option<&BasePeer> {
-        self.peers.iter().find(|v| &v.id == address)
+        self.index.get(address).map(|i| &self.peers[*i])
     }
 
     fn get_by_index(&self, index: usize) -> Option<&BasePeer> {
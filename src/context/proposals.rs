@@ -1,8 +1,10 @@
 use std::fmt;
 
+use bytes::BufMut;
 use malachite_core_types::Round;
 
 use crate::context::address::BasePeerAddress;
+use crate::context::encoding::{encode_header, put_round, DecodingError, MessageTag, Reader};
 use crate::context::height::BaseHeight;
 use crate::context::value::BaseValue;
 use crate::context::BaseContext;
@@ -14,17 +16,44 @@ pub struct BaseProposal {
     pub value: BaseValue,
     pub proposer: BasePeerAddress,
     pub round: Round,
-    // Todo: Clarify if needs to be exposed here or at lower levels
-    //  of abstraction?
-    // pub pol_round: Round,
+    /// The proof-of-lock round, i.e., the round in which the proposer saw a
+    /// polka for this value; [`Round::Nil`] if the value is fresh.
+    pub pol_round: Round,
 }
 
 impl BaseProposal {
-    // Todo: We should be marshaling to bytes all fields here
-    //  not just the value
-    pub fn to_bytes(&self) -> [u8; size_of::<u64>()] {
-        // Serialize just the value, a u64
-        self.value.0.to_be_bytes()
+    /// The canonical encoding of this proposal, i.e., the payload to sign.
+    /// Covers every field, in order: height, round, value, pol_round, and proposer.
+    /// See [`crate::context::encoding`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = encode_header(MessageTag::Proposal);
+
+        buf.put_u64(self.height.0);
+        put_round(&mut buf, self.round);
+        buf.put_u64(self.value.0);
+        put_round(&mut buf, self.pol_round);
+        buf.put_u32(self.proposer.0);
+
+        buf
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<BaseProposal, DecodingError> {
+        let (_, mut reader) = Reader::new(bytes, &[MessageTag::Proposal])?;
+
+        let height = BaseHeight(reader.u64()?);
+        let round = reader.round()?;
+        let value = BaseValue(reader.u64()?);
+        let pol_round = reader.round()?;
+        let proposer = BasePeerAddress(reader.u32()?);
+        reader.finish()?;
+
+        Ok(BaseProposal {
+            height,
+            value,
+            proposer,
+            round,
+            pol_round,
+        })
     }
 }
 
@@ -32,8 +61,8 @@ impl fmt::Display for BaseProposal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Proposal / {} / {} / {:?} / <- {}",
-            self.height, self.round, self.value, self.proposer
+            "Proposal / {} / {} / {:?} / pol {} / <- {}",
+            self.height, self.round, self.value, self.pol_round, self.proposer
         )
     }
 }
@@ -58,11 +87,8 @@ impl malachite_core_types::Proposal<BaseContext> for BaseProposal {
         self.value
     }
 
-    // Todo: Seems like exactly the kind of stuff we can
-    //  abstract in a "base" layer of primitives ?
     fn pol_round(&self) -> Round {
-        // We assume we never need to go into round > 0
-        Round::Nil
+        self.pol_round
     }
 
     fn validator_address(&self) -> &BasePeerAddress {
@@ -70,28 +96,110 @@ impl malachite_core_types::Proposal<BaseContext> for BaseProposal {
     }
 }
 
-/// A part of a proposal
+/// What a part of a proposal carries, see [`BaseProposalPart`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PartContent {
+    /// Opens the stream of a value, with the proof-of-lock round of the value.
+    Init { valid_round: Round },
+    /// A chunk of the bytes of the value.
+    Data(Vec<u8>),
+    /// Closes the stream.
+    Fin,
+}
+
+/// A part of a proposal. The proposer of a value streams it as a sequence
+/// of parts: an init, then the bytes of the value in chunks, then a fin.
+/// See [`crate::streaming`].
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct BaseProposalPart {
     pub height: BaseHeight,
-    // No need for anything else, for the moment
+    pub round: Round,
+    pub proposer: BasePeerAddress,
+    /// The position of this part in its stream, from 0.
+    pub sequence: u64,
+    pub content: PartContent,
 }
 
-#[allow(unused)]
 impl BaseProposalPart {
-    pub fn new(h: BaseHeight) -> Self {
-        Self { height: h }
+    /// The canonical encoding of this part, i.e., the payload to sign.
+    /// Covers every field, in order: height, round, proposer, sequence, and content.
+    /// The content starts with its kind: 0 for init, 1 for data and 2 for fin.
+    /// See [`crate::context::encoding`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = encode_header(MessageTag::ProposalPart);
+
+        buf.put_u64(self.height.0);
+        put_round(&mut buf, self.round);
+        buf.put_u32(self.proposer.0);
+        buf.put_u64(self.sequence);
+        match &self.content {
+            PartContent::Init { valid_round } => {
+                buf.put_u8(0);
+                put_round(&mut buf, *valid_round);
+            }
+            PartContent::Data(data) => {
+                buf.put_u8(1);
+                buf.put_u32(data.len() as u32);
+                buf.put_slice(data);
+            }
+            PartContent::Fin => buf.put_u8(2),
+        }
+
+        buf
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<BaseProposalPart, DecodingError> {
+        let (_, mut reader) = Reader::new(bytes, &[MessageTag::ProposalPart])?;
+
+        let height = BaseHeight(reader.u64()?);
+        let round = reader.round()?;
+        let proposer = BasePeerAddress(reader.u32()?);
+        let sequence = reader.u64()?;
+        let content = match reader.u8()? {
+            0 => PartContent::Init {
+                valid_round: reader.round()?,
+            },
+            1 => {
+                let len = reader.u32()? as usize;
+                PartContent::Data(reader.bytes(len)?.to_vec())
+            }
+            2 => PartContent::Fin,
+            kind => return Err(DecodingError::UnknownPartKind(kind)),
+        };
+        reader.finish()?;
+
+        Ok(BaseProposalPart {
+            height,
+            round,
+            proposer,
+            sequence,
+            content,
+        })
+    }
+}
+
+impl fmt::Display for BaseProposalPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Part / {} / {} / #{} / ",
+            self.height, self.round, self.sequence
+        )?;
+        match &self.content {
+            PartContent::Init { valid_round } => write!(f, "init pol {valid_round}")?,
+            PartContent::Data(data) => write!(f, "data {} bytes", data.len())?,
+            PartContent::Fin => write!(f, "fin")?,
+        }
+        write!(f, " / <- {}", self.proposer)
     }
 }
 
 impl malachite_core_types::ProposalPart<BaseContext> for BaseProposalPart {
     fn is_first(&self) -> bool {
-        true
+        matches!(self.content, PartContent::Init { .. })
     }
 
     fn is_last(&self) -> bool {
-        // Todo: Why is this needed?
-        //  Maybe just needed in case of streaming proposals?
-        unimplemented!()
+        matches!(self.content, PartContent::Fin)
     }
 }
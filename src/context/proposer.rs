@@ -0,0 +1,239 @@
+/// Strategies for selecting the proposer of each height and round.
+///
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use malachite_core_types::{Round, Validator, ValidatorSet};
+
+use crate::context::height::BaseHeight;
+use crate::context::peer::BasePeer;
+use crate::context::peer_set::BasePeerSet;
+
+/// How [`crate::context::BaseContext`] selects the proposer among the peers.
+///
+/// Every strategy is a deterministic function of the peer set, height and round,
+/// so that all peers agree on the proposer without exchanging any message.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProposerSelection {
+    /// The proposer is always the first peer.
+    #[default]
+    Fixed,
+
+    /// Peers take turns, moving to the next peer with every height and every round.
+    RoundRobin,
+
+    /// The proposer priority algorithm of Tendermint: peers take turns
+    /// proportionally to their voting power.
+    WeightedPriority,
+
+    /// A random peer, weighted by voting power, drawn from a generator
+    /// seeded with the given seed, the height and the round.
+    SeededRandom(u64),
+}
+
+impl ProposerSelection {
+    pub fn select<'a>(
+        &self,
+        peer_set: &'a BasePeerSet,
+        height: BaseHeight,
+        round: Round,
+    ) -> &'a BasePeer {
+        assert!(peer_set.count() > 0, "no peer found in the validator set");
+
+        // The first round of a height is round 0, never nil
+        let round = round.as_u32().unwrap_or(0) as u64;
+
+        let index = match self {
+            ProposerSelection::Fixed => 0,
+            ProposerSelection::RoundRobin => {
+                (height.0.wrapping_add(round) % peer_set.count() as u64) as usize
+            }
+            ProposerSelection::WeightedPriority => {
+                weighted_priority_index(peer_set, height.0.wrapping_add(round))
+            }
+            ProposerSelection::SeededRandom(seed) => {
+                seeded_random_index(peer_set, *seed, height.0, round)
+            }
+        };
+
+        peer_set
+            .get_by_index(index)
+            .expect("no peer found in the validator set")
+    }
+}
+
+/// The proposer of each turn of the proposer priority algorithm of Tendermint,
+/// by index in `peers`, over one cycle of turns. At each turn, every peer's
+/// priority grows by its voting power, then the peer with the highest priority
+/// proposes and its priority drops by the total voting power.
+///
+/// Once each peer has proposed as many times as its voting power, divided by
+/// the greatest common divisor of the voting powers, the priorities are back
+/// to zero and the turns repeat. Without any voting power, there is no turn.
+pub fn weighted_rotation(peers: &[BasePeer]) -> Vec<usize> {
+    let divisor = peers.iter().fold(0, |d, p| gcd(d, p.voting_power()));
+    if divisor == 0 {
+        return vec![];
+    }
+
+    let powers: Vec<i128> = peers
+        .iter()
+        .map(|p| (p.voting_power() / divisor) as i128)
+        .collect();
+    let total: i128 = powers.iter().sum();
+
+    let mut priorities = vec![0i128; peers.len()];
+    let mut rotation = Vec::with_capacity(total as usize);
+
+    for _ in 0..total {
+        for (priority, power) in priorities.iter_mut().zip(&powers) {
+            *priority += power;
+        }
+
+        // Ties go to the peer which comes first in the set
+        let proposer = priorities
+            .iter()
+            .enumerate()
+            .max_by(|(i, a), (j, b)| a.cmp(b).then(j.cmp(i)))
+            .map(|(i, _)| i)
+            .unwrap();
+
+        priorities[proposer] -= total;
+        rotation.push(proposer);
+    }
+
+    rotation
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// The index of the proposer of `turn`, counting from 0, in the rotation of the
+// peer set.
+fn weighted_priority_index(peer_set: &BasePeerSet, turn: u64) -> usize {
+    let rotation = peer_set.proposer_rotation();
+    rotation[(turn % rotation.len() as u64) as usize]
+}
+
+fn seeded_random_index(peer_set: &BasePeerSet, seed: u64, height: u64, round: u64) -> usize {
+    let mut rng = StdRng::seed_from_u64(seed ^ height.rotate_left(32) ^ round);
+    let mut target = rng.gen_range(0..peer_set.total_voting_power());
+
+    for (i, peer) in peer_set.peers().iter().enumerate() {
+        if target < peer.voting_power() {
+            return i;
+        }
+        target -= peer.voting_power();
+    }
+
+    unreachable!("the total voting power is the sum of the peers' voting power")
+}
+
+#[cfg(test)]
+mod tests {
+    use malachite_core_types::Round;
+
+    use crate::context::height::BaseHeight;
+    use crate::context::peer_set::BasePeerSet;
+    use crate::context::proposer::ProposerSelection;
+    use crate::context::signing_provider::BaseSigningProvider;
+
+    fn proposers(selection: ProposerSelection, set: &BasePeerSet) -> Vec<(u32, u32, u32)> {
+        let mut proposers = vec![];
+        for height in 0..3 {
+            for round in 0..3 {
+                let peer = selection.select(set, BaseHeight(height), Round::new(round));
+                proposers.push((height as u32, round, peer.id.0));
+            }
+        }
+        proposers
+    }
+
+    fn peer_set(size: u32) -> BasePeerSet {
+        BasePeerSet::new(
+            (0..size)
+                .map(|i| BaseSigningProvider::derive(0, i).public_key())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn proposer_changes_across_heights_and_rounds() {
+        let set = peer_set(4);
+
+        for selection in [
+            ProposerSelection::RoundRobin,
+            ProposerSelection::WeightedPriority,
+        ] {
+            let p = proposers(selection, &set);
+
+            // With equal voting power, each height and each round moves to the next peer
+            for (height, round, peer) in p {
+                assert_eq!(peer, (height + round) % 4, "{:?}", selection);
+            }
+        }
+
+        // Always the same peer
+        assert!(proposers(ProposerSelection::Fixed, &set)
+            .iter()
+            .all(|(_, _, peer)| *peer == 0));
+    }
+
+    #[test]
+    fn weighted_priority_scales_to_large_voting_powers() {
+        let keys = |n| (0..n).map(|i| BaseSigningProvider::derive(0, i).public_key());
+        let small = BasePeerSet::with_voting_powers(keys(3).collect(), vec![3, 2, 1]);
+        let scale = 1_000_000_000_000;
+        let large =
+            BasePeerSet::with_voting_powers(keys(3).collect(), vec![3 * scale, 2 * scale, scale]);
+
+        // Scaling the voting powers scales the priorities, but not the turns,
+        // whichever order the turns are selected in
+        let selection = ProposerSelection::WeightedPriority;
+        for height in (0..600).rev() {
+            let round = Round::new(0);
+            assert_eq!(
+                selection.select(&small, BaseHeight(height), round).id,
+                selection.select(&large, BaseHeight(height), round).id,
+            );
+        }
+
+        // The rotation of the scaled powers is as short as that of the small ones
+        assert_eq!(large.proposer_rotation(), small.proposer_rotation());
+        assert_eq!(small.proposer_rotation(), &[0, 1, 0, 2, 1, 0]);
+
+        // With equal voting powers, the peers take turns in order
+        let many = BasePeerSet::with_voting_powers(keys(100).collect(), vec![scale; 100]);
+        for height in 0..5000 {
+            for round in 0..2 {
+                let peer = selection.select(&many, BaseHeight(height), Round::new(round));
+                assert_eq!(peer.id.0 as u64, (height + round as u64) % 100);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "no voting power")]
+    fn peer_sets_without_voting_power_are_rejected() {
+        let keys = (0..3)
+            .map(|i| BaseSigningProvider::derive(0, i).public_key())
+            .collect();
+        BasePeerSet::with_voting_powers(keys, vec![0; 3]);
+    }
+
+    #[test]
+    fn seeded_random_proposer_is_reproducible() {
+        let set = peer_set(4);
+
+        let first = proposers(ProposerSelection::SeededRandom(7), &set);
+        assert_eq!(first, proposers(ProposerSelection::SeededRandom(7), &set));
+
+        // Not always the same peer
+        assert!(first.iter().any(|(_, _, peer)| *peer != first[0].2));
+    }
+}
@@ -1,29 +1,57 @@
-use rand::rngs::OsRng;
+use rand::rngs::{OsRng, StdRng};
+use rand::SeedableRng;
 use tracing::debug;
 
-use malachite_core_types::{SignedMessage, SigningProvider};
+use malachite_core_types::{
+    CertificateError, NilOrVal, SignedMessage, SigningProvider, Validator, VoteType,
+};
 
 use super::{
     signing_scheme::{PrivateKey, PublicKey},
     BaseContext,
 };
 use crate::context::signing_scheme::Ed25519;
+use crate::context::vote::BaseVote;
 
 #[derive(Clone)]
 pub struct BaseSigningProvider {
     private_key: PrivateKey,
 }
 
+impl Default for BaseSigningProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BaseSigningProvider {
+    /// Creates a signing provider with a fresh, random, private key.
+    /// Simulations never use it, as their keys derive from their seed,
+    /// see [`BaseSigningProvider::derive`].
     pub fn new() -> BaseSigningProvider {
         let cprng = OsRng;
         let signing_key = Ed25519::generate_keypair(cprng);
 
-        debug!(public_key = ?signing_key.public_key(), "created new signing provider");
+        Self::from_private_key(signing_key)
+    }
 
-        Self {
-            private_key: signing_key,
-        }
+    /// Creates a signing provider whose private key derives deterministically
+    /// from `seed` and the `index` of the peer, so that runs are reproducible
+    /// and peers with different indices get different keys.
+    pub fn derive(seed: u64, index: u32) -> BaseSigningProvider {
+        let mut rng_seed = [0u8; 32];
+        rng_seed[..8].copy_from_slice(&seed.to_be_bytes());
+        rng_seed[8..12].copy_from_slice(&index.to_be_bytes());
+
+        let signing_key = Ed25519::generate_keypair(StdRng::from_seed(rng_seed));
+
+        Self::from_private_key(signing_key)
+    }
+
+    pub fn from_private_key(private_key: PrivateKey) -> BaseSigningProvider {
+        debug!(public_key = ?private_key.public_key(), "created new signing provider");
+
+        Self { private_key }
     }
 
     pub fn public_key(&self) -> PublicKey {
@@ -31,7 +59,6 @@ impl BaseSigningProvider {
     }
 }
 
-#[allow(unused)]
 impl SigningProvider<BaseContext> for BaseSigningProvider {
     fn sign_vote(
         &self,
@@ -47,7 +74,7 @@ impl SigningProvider<BaseContext> for BaseSigningProvider {
         signature: &malachite_core_types::Signature<BaseContext>,
         public_key: &malachite_core_types::PublicKey<BaseContext>,
     ) -> bool {
-        todo!()
+        public_key.verify(&vote.to_bytes(), signature).is_ok()
     }
 
     fn sign_proposal(
@@ -64,7 +91,7 @@ impl SigningProvider<BaseContext> for BaseSigningProvider {
         signature: &malachite_core_types::Signature<BaseContext>,
         public_key: &malachite_core_types::PublicKey<BaseContext>,
     ) -> bool {
-        todo!()
+        public_key.verify(&proposal.to_bytes(), signature).is_ok()
     }
 
     fn sign_proposal_part(
@@ -74,7 +101,8 @@ impl SigningProvider<BaseContext> for BaseSigningProvider {
         BaseContext,
         <BaseContext as malachite_core_types::Context>::ProposalPart,
     > {
-        todo!()
+        let signature = self.private_key.sign(&proposal_part.to_bytes());
+        SignedMessage::new(proposal_part, signature)
     }
 
     fn verify_signed_proposal_part(
@@ -83,9 +111,13 @@ impl SigningProvider<BaseContext> for BaseSigningProvider {
         signature: &malachite_core_types::Signature<BaseContext>,
         public_key: &malachite_core_types::PublicKey<BaseContext>,
     ) -> bool {
-        todo!()
+        public_key
+            .verify(&proposal_part.to_bytes(), signature)
+            .is_ok()
     }
 
+    // Reconstructs the precommit which the validator signed, then verifies
+    // the signature of that precommit.
     fn verify_commit_signature(
         &self,
         certificate: &malachite_core_types::CommitCertificate<BaseContext>,
@@ -95,6 +127,58 @@ impl SigningProvider<BaseContext> for BaseSigningProvider {
         malachite_core_types::VotingPower,
         malachite_core_types::CertificateError<BaseContext>,
     > {
-        todo!()
+        let precommit = BaseVote {
+            vote_type: VoteType::Precommit,
+            height: certificate.height,
+            value_id: NilOrVal::Val(certificate.value_id),
+            round: certificate.round,
+            voter: commit_sig.address,
+            extension: commit_sig.extension.clone(),
+        };
+
+        if !self.verify_signed_vote(&precommit, &commit_sig.signature, validator.public_key()) {
+            return Err(CertificateError::InvalidSignature(commit_sig.clone()));
+        }
+
+        Ok(validator.voting_power())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use malachite_core_types::{NilOrVal, Round, SigningProvider, VoteType};
+
+    use crate::context::address::BasePeerAddress;
+    use crate::context::height::BaseHeight;
+    use crate::context::signing_provider::BaseSigningProvider;
+    use crate::context::value::BaseValueId;
+    use crate::context::vote::BaseVote;
+
+    #[test]
+    fn derived_keys_are_reproducible_and_distinct() {
+        let first = BaseSigningProvider::derive(42, 0).public_key();
+
+        assert_eq!(first, BaseSigningProvider::derive(42, 0).public_key());
+        assert_ne!(first, BaseSigningProvider::derive(42, 1).public_key());
+        assert_ne!(first, BaseSigningProvider::derive(43, 0).public_key());
+    }
+
+    #[test]
+    fn signatures_verify_only_against_the_signer_key() {
+        let signer = BaseSigningProvider::derive(42, 0);
+        let other = BaseSigningProvider::derive(42, 1);
+
+        let vote = BaseVote {
+            vote_type: VoteType::Prevote,
+            height: BaseHeight(3),
+            value_id: NilOrVal::Val(BaseValueId(45)),
+            round: Round::new(0),
+            voter: BasePeerAddress(0),
+            extension: None,
+        };
+        let signed = signer.sign_vote(vote.clone());
+
+        assert!(other.verify_signed_vote(&vote, &signed.signature, &signer.public_key()));
+        assert!(!signer.verify_signed_vote(&vote, &signed.signature, &other.public_key()));
     }
 }
@@ -129,6 +129,18 @@ impl PublicKey {
             .verify(signature.inner(), msg)
             .map_err(|_| signature::Error::new())
     }
+
+    pub fn as_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+}
+
+impl TryFrom<&[u8]> for PublicKey {
+    type Error = ed25519_consensus::Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Ok(Self(ed25519_consensus::VerificationKey::try_from(bytes)?))
+    }
 }
 
 impl Verifier<Signature> for PublicKey {
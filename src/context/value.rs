@@ -5,7 +5,7 @@ pub struct BaseValue(pub u64);
 
 // Todo: Is this overkill?
 //  Seems necessary for fulfilling Vote::value().
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Ord, PartialOrd)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Ord, PartialOrd, Hash)]
 pub struct BaseValueId(pub u64);
 
 impl malachite_core_types::Value for BaseValue {
@@ -1,7 +1,11 @@
-use malachite_core_types::{NilOrVal, Round, SignedExtension, VoteType};
+use bytes::{BufMut, Bytes};
+use malachite_core_types::{Extension, NilOrVal, Round, SignedExtension, SignedMessage, VoteType};
 use std::fmt;
 
 use crate::context::address::BasePeerAddress;
+use crate::context::encoding::{
+    encode_header, put_flag, put_round, DecodingError, MessageTag, Reader,
+};
 use crate::context::height::BaseHeight;
 use crate::context::value::BaseValueId;
 use crate::context::BaseContext;
@@ -17,12 +21,73 @@ pub struct BaseVote {
 }
 
 impl BaseVote {
-    // TODO: Similar to how we do it for `BaseProposal`, serialize only
-    //  the height here as a quick prototype.
-    // This is fine because we use `to_bytes`` only to get the payload to sign, not for
-    // networking payloads.
-    pub fn to_bytes(&self) -> [u8; size_of::<u64>()] {
-        self.height.0.to_be_bytes()
+    /// The canonical encoding of this vote, i.e., the payload to sign.
+    /// Covers every field, in order: type (in the header), height, round,
+    /// value id or nil, voter, and extension.
+    /// See [`crate::context::encoding`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let tag = match self.vote_type {
+            VoteType::Prevote => MessageTag::Prevote,
+            VoteType::Precommit => MessageTag::Precommit,
+        };
+        let mut buf = encode_header(tag);
+
+        buf.put_u64(self.height.0);
+        put_round(&mut buf, self.round);
+        match self.value_id {
+            NilOrVal::Nil => put_flag(&mut buf, false),
+            NilOrVal::Val(id) => {
+                put_flag(&mut buf, true);
+                buf.put_u64(id.0);
+            }
+        }
+        buf.put_u32(self.voter.0);
+        match &self.extension {
+            None => put_flag(&mut buf, false),
+            Some(ext) => {
+                put_flag(&mut buf, true);
+                buf.put_u32(ext.message.data.len() as u32);
+                buf.put_slice(&ext.message.data);
+                buf.put_slice(&ext.signature.as_bytes());
+            }
+        }
+
+        buf
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<BaseVote, DecodingError> {
+        let (tag, mut reader) = Reader::new(bytes, &[MessageTag::Prevote, MessageTag::Precommit])?;
+        let vote_type = match tag {
+            MessageTag::Prevote => VoteType::Prevote,
+            _ => VoteType::Precommit,
+        };
+
+        let height = BaseHeight(reader.u64()?);
+        let round = reader.round()?;
+        let value_id = match reader.flag()? {
+            false => NilOrVal::Nil,
+            true => NilOrVal::Val(BaseValueId(reader.u64()?)),
+        };
+        let voter = BasePeerAddress(reader.u32()?);
+        let extension = match reader.flag()? {
+            false => None,
+            true => {
+                let len = reader.u32()? as usize;
+                let data = Bytes::copy_from_slice(reader.bytes(len)?);
+                let signature = reader.signature()?;
+                Some(SignedMessage::new(Extension::new(data), signature))
+            }
+        };
+        reader.finish()?;
+
+        Ok(BaseVote {
+            vote_type,
+            height,
+            value_id,
+            round,
+            voter,
+            extension,
+        })
     }
 }
 
@@ -1,3 +1,7 @@
+use std::time::Duration;
+
+use malachite_core_types::Round;
+
 use crate::context::address::BasePeerAddress;
 use crate::context::height::BaseHeight;
 use crate::context::value::BaseValueId;
@@ -7,9 +11,17 @@ use crate::context::value::BaseValueId;
 ///
 /// The full value is not captured here, merely the
 /// identifier of that value: [`BaseValueId`].
-#[derive(Debug)]
+///
+/// Times are simulated, as measured by the virtual clock of the simulator.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Decision {
     pub peer: BasePeerAddress,
     pub value_id: BaseValueId,
     pub height: BaseHeight,
+    /// The round in which consensus decided.
+    pub round: Round,
+    /// The moment this decision took place.
+    pub time: Duration,
+    /// How long it took the peer to decide, since it started this height.
+    pub elapsed: Duration,
 }
@@ -0,0 +1,248 @@
+/// Online checks of the safety properties of consensus, against
+/// every decision as soon as a peer takes it.
+///
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::Duration;
+
+use tracing::error;
+
+use crate::context::address::BasePeerAddress;
+use crate::context::height::BaseHeight;
+use crate::context::value::BaseValueId;
+use crate::decision::Decision;
+
+/// A safety property which some [`Decision`] violates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// Agreement: two peers decided different values at the same height.
+    Disagreement {
+        height: BaseHeight,
+        first: (BasePeerAddress, BaseValueId),
+        second: (BasePeerAddress, BaseValueId),
+    },
+
+    /// Integrity: a peer decided twice at the same height.
+    DoubleDecision {
+        peer: BasePeerAddress,
+        height: BaseHeight,
+        first: BaseValueId,
+        second: BaseValueId,
+    },
+
+    /// Validity: a peer decided a value which no peer was given to propose.
+    UnproposedValue {
+        peer: BasePeerAddress,
+        height: BaseHeight,
+        value_id: BaseValueId,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Disagreement {
+                height,
+                first,
+                second,
+            } => write!(
+                f,
+                "disagreement at height {height}: {} decided {}, {} decided {}",
+                first.0, first.1, second.0, second.1
+            ),
+            Violation::DoubleDecision {
+                peer,
+                height,
+                first,
+                second,
+            } => write!(
+                f,
+                "{peer} decided twice at height {height}: {first}, then {second}"
+            ),
+            Violation::UnproposedValue {
+                peer,
+                height,
+                value_id,
+            } => write!(
+                f,
+                "{peer} decided {value_id} at height {height}, which was never proposed"
+            ),
+        }
+    }
+}
+
+/// A [`Violation`], along with when it happened: the step of the simulator
+/// which delivered the envelope leading to the offending decision,
+/// and the simulated time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ViolationReport {
+    pub violation: Violation,
+    pub step: u64,
+    pub time: Duration,
+}
+
+/// Watches decisions and flags every violation of agreement, integrity,
+/// and validity, as soon as the decision which violates it takes place.
+///
+/// Validity holds if a peer decides a value which some peer was given
+/// to propose, see [`SafetyChecker::proposed`].
+/// Values which a Byzantine peer makes up are never valid.
+#[derive(Debug, Default)]
+pub struct SafetyChecker {
+    // The first decision at each height.
+    decided: HashMap<BaseHeight, (BasePeerAddress, BaseValueId)>,
+
+    // The decision of each peer at each height.
+    decisions: HashMap<(BasePeerAddress, BaseHeight), BaseValueId>,
+
+    // The values which peers were given to propose.
+    proposed: HashSet<BaseValueId>,
+
+    violations: Vec<ViolationReport>,
+}
+
+impl SafetyChecker {
+    /// Records that a peer was given `value_id` to propose.
+    pub fn proposed(&mut self, value_id: BaseValueId) {
+        self.proposed.insert(value_id);
+    }
+
+    /// Checks `decision`, which took place at the given step.
+    /// Returns the number of violations it leads to.
+    pub fn check(&mut self, decision: &Decision, step: u64) -> usize {
+        let mut found = vec![];
+
+        if !self.proposed.contains(&decision.value_id) {
+            found.push(Violation::UnproposedValue {
+                peer: decision.peer,
+                height: decision.height,
+                value_id: decision.value_id,
+            });
+        }
+
+        // A peer which decides twice does not disagree with itself:
+        // agreement holds between the first decisions of peers
+        match self.decisions.get(&(decision.peer, decision.height)) {
+            Some(first) => found.push(Violation::DoubleDecision {
+                peer: decision.peer,
+                height: decision.height,
+                first: *first,
+                second: decision.value_id,
+            }),
+            None => {
+                self.decisions
+                    .insert((decision.peer, decision.height), decision.value_id);
+                self.check_agreement(decision, &mut found);
+            }
+        }
+
+        let count = found.len();
+        for violation in found {
+            error!(step, time = ?decision.time, "safety violation: {violation}");
+
+            self.violations.push(ViolationReport {
+                violation,
+                step,
+                time: decision.time,
+            });
+        }
+
+        count
+    }
+
+    // Checks the first decision of a peer at a height against the first
+    // decision of any peer at that height.
+    fn check_agreement(&mut self, decision: &Decision, found: &mut Vec<Violation>) {
+        match self.decided.get(&decision.height) {
+            Some(first) if first.1 != decision.value_id => found.push(Violation::Disagreement {
+                height: decision.height,
+                first: *first,
+                second: (decision.peer, decision.value_id),
+            }),
+            Some(_) => {}
+            None => {
+                self.decided
+                    .insert(decision.height, (decision.peer, decision.value_id));
+            }
+        }
+    }
+
+    /// Every violation found so far, in the order they happened.
+    pub fn violations(&self) -> &[ViolationReport] {
+        &self.violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use malachite_core_types::Round;
+
+    use crate::context::address::BasePeerAddress;
+    use crate::context::height::BaseHeight;
+    use crate::context::value::BaseValueId;
+    use crate::decision::Decision;
+    use crate::invariants::{SafetyChecker, Violation};
+
+    fn decision(peer: u32, height: u64, value: u64) -> Decision {
+        Decision {
+            peer: BasePeerAddress(peer),
+            value_id: BaseValueId(value),
+            height: BaseHeight(height),
+            round: Round::new(0),
+            time: Duration::ZERO,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn checker_flags_each_kind_of_violation() {
+        let mut checker = SafetyChecker::default();
+        checker.proposed(BaseValueId(45));
+        checker.proposed(BaseValueId(46));
+
+        assert_eq!(checker.check(&decision(0, 0, 45), 10), 0);
+        assert_eq!(checker.check(&decision(1, 0, 45), 11), 0);
+
+        assert_eq!(checker.check(&decision(2, 0, 46), 12), 1);
+        assert_eq!(checker.check(&decision(0, 0, 46), 13), 1);
+        assert_eq!(checker.check(&decision(3, 1, 47), 14), 1);
+
+        let violations: Vec<_> = checker
+            .violations()
+            .iter()
+            .map(|r| (r.step, r.violation.clone()))
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                (
+                    12,
+                    Violation::Disagreement {
+                        height: BaseHeight(0),
+                        first: (BasePeerAddress(0), BaseValueId(45)),
+                        second: (BasePeerAddress(2), BaseValueId(46)),
+                    }
+                ),
+                (
+                    13,
+                    Violation::DoubleDecision {
+                        peer: BasePeerAddress(0),
+                        height: BaseHeight(0),
+                        first: BaseValueId(45),
+                        second: BaseValueId(46),
+                    }
+                ),
+                (
+                    14,
+                    Violation::UnproposedValue {
+                        peer: BasePeerAddress(3),
+                        height: BaseHeight(1),
+                        value_id: BaseValueId(47),
+                    }
+                ),
+            ]
+        );
+    }
+}
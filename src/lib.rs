@@ -0,0 +1,59 @@
+/// A simulator of networks of peers running the Malachite core library.
+///
+/// The approach is to simulate everything: the network, signing, mempool, ...
+///
+/// The [`Simulator`] ties together the execution of the Malachite core library
+/// across multiple peers, under simulated conditions. Each peer is an
+/// [`Application`], a simple data structure running in the same thread as all
+/// the other peers, with its own copy of the [`BaseContext`].
+///
+/// A [`SimulatorBuilder`] configures a simulator, for instance:
+///
+/// ```
+/// use malachite_simulator::{SimulatorBuilder, StopConditions, Workload};
+/// use malachite_simulator::context::height::BaseHeight;
+///
+/// let (mut n, mut states, _proposals, _decisions) = SimulatorBuilder::new(4)
+///     .seed(7)
+///     .workload(Workload::Sequential(45))
+///     .build();
+///
+/// let stop = StopConditions {
+///     height: Some(BaseHeight(2)),
+///     ..Default::default()
+/// };
+/// let report = n.run(&mut states, stop);
+/// assert!(report.is_success());
+/// ```
+///
+/// See the top-level README.md for more details.
+pub mod application;
+pub mod builder;
+pub mod byzantine;
+pub mod clock;
+pub mod common;
+pub mod context;
+pub mod decision;
+pub mod invariants;
+pub mod liveness;
+pub mod observer;
+pub mod partition;
+pub mod report;
+pub mod scenario;
+pub mod simulator;
+pub mod streaming;
+pub mod timers;
+pub mod trace;
+pub mod validators;
+
+pub use application::Application;
+pub use builder::SimulatorBuilder;
+pub use context::BaseContext;
+pub use decision::Decision;
+pub use observer::Observer;
+pub use report::{CancelHandle, SimulationReport, StopConditions, StopReason};
+pub use scenario::Scenario;
+pub use simulator::{
+    ConsensusParams, DecisionsReceiver, LinkFaults, NetworkModel, PeerMode, ProposalsSender,
+    Simulator, States, Workload,
+};
@@ -0,0 +1,191 @@
+/// Detection of stalls, where consensus stops making progress,
+/// and snapshots of the state of peers to diagnose them.
+///
+use std::fmt;
+use std::time::Duration;
+
+use malachite_core_consensus::State;
+use malachite_core_types::Round;
+
+use crate::context::address::BasePeerAddress;
+use crate::context::height::BaseHeight;
+use crate::context::value::BaseValue;
+use crate::context::BaseContext;
+use crate::simulator::PeerMode;
+
+/// How long the simulation may go on without any decision before
+/// the [`LivenessMonitor`] reports a stall.
+/// Either limit may be unset, and by default both are.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StallLimits {
+    /// At most this many steps between two decisions.
+    pub steps: Option<u64>,
+
+    /// At most this much simulated time between two decisions.
+    pub time: Option<Duration>,
+}
+
+/// The reason why the simulation stalls.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Stall {
+    /// No peer decided within the limits since the last decision,
+    /// or since the simulation started if there was none.
+    NoDecision { steps: u64, elapsed: Duration },
+
+    /// There is nothing left to simulate, i.e., no envelope in flight and no
+    /// pending timeout at any peer, although heights remain undecided.
+    Quiescent,
+}
+
+impl fmt::Display for Stall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stall::NoDecision { steps, elapsed } => {
+                write!(f, "no decision for {steps} steps and {elapsed:?}")
+            }
+            Stall::Quiescent => write!(f, "nothing left to simulate"),
+        }
+    }
+}
+
+/// The consensus state of a peer, as far as progress is concerned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeerSnapshot {
+    pub peer: BasePeerAddress,
+    pub height: BaseHeight,
+    pub round: Round,
+    pub step: String,
+    /// The value the peer is locked on, and the round it locked in.
+    pub locked: Option<(BaseValue, Round)>,
+    /// The latest value for which the peer saw a polka, and the round of the polka.
+    pub valid: Option<(BaseValue, Round)>,
+    pub mode: PeerMode,
+}
+
+impl PeerSnapshot {
+    pub fn of(state: &State<BaseContext>, mode: PeerMode) -> Self {
+        let round_state = state.driver.round_state();
+
+        Self {
+            peer: state.params.address,
+            height: state.height(),
+            round: state.round(),
+            step: format!("{:?}", state.driver.step()),
+            locked: round_state.locked.as_ref().map(|l| (l.value, l.round)),
+            valid: round_state.valid.as_ref().map(|v| (v.value, v.round)),
+            mode,
+        }
+    }
+}
+
+impl fmt::Display for PeerSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pretty = |rv: &Option<(BaseValue, Round)>| match rv {
+            Some((value, round)) => format!("{} in round {}", value.0, round),
+            None => "none".to_string(),
+        };
+
+        write!(
+            f,
+            "{} ({:?}): height {}, round {}, step {}, locked {}, valid {}",
+            self.peer,
+            self.mode,
+            self.height,
+            self.round,
+            self.step,
+            pretty(&self.locked),
+            pretty(&self.valid)
+        )
+    }
+}
+
+/// A stall, along with when it happened and the state of every peer then.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StallReport {
+    pub stall: Stall,
+    pub step: u64,
+    pub time: Duration,
+    pub peers: Vec<PeerSnapshot>,
+}
+
+impl fmt::Display for StallReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "stalled at step {} ({:?}): {}",
+            self.step, self.time, self.stall
+        )?;
+        for peer in self.peers.iter() {
+            write!(f, "\n  {peer}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Watches the progress of the simulation, and detects when it stalls.
+#[derive(Debug, Default)]
+pub struct LivenessMonitor {
+    pub limits: StallLimits,
+
+    // The step and simulated time of the latest decision, or of the latest stall.
+    since_step: u64,
+    since_time: Duration,
+}
+
+impl LivenessMonitor {
+    /// Records that some peer decided at the given step and time.
+    pub fn decided(&mut self, step: u64, time: Duration) {
+        self.since_step = step;
+        self.since_time = time;
+    }
+
+    /// Checks whether the simulation exceeds the limits at the given
+    /// step and time. Once it reports a stall, the monitor starts counting
+    /// anew, as if a decision took place.
+    pub fn check(&mut self, step: u64, time: Duration) -> Option<Stall> {
+        let steps = step - self.since_step;
+        let elapsed = time - self.since_time;
+
+        let stalled = self.limits.steps.is_some_and(|limit| steps > limit)
+            || self.limits.time.is_some_and(|limit| elapsed > limit);
+
+        stalled.then(|| {
+            self.decided(step, time);
+            Stall::NoDecision { steps, elapsed }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::liveness::{LivenessMonitor, Stall, StallLimits};
+
+    #[test]
+    fn monitor_reports_no_decision_past_the_limits() {
+        let mut monitor = LivenessMonitor {
+            limits: StallLimits {
+                steps: Some(10),
+                time: Some(Duration::from_secs(5)),
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(monitor.check(10, Duration::from_secs(1)), None);
+        monitor.decided(10, Duration::from_secs(1));
+        assert_eq!(monitor.check(20, Duration::from_secs(6)), None);
+
+        assert_eq!(
+            monitor.check(21, Duration::from_secs(6)),
+            Some(Stall::NoDecision {
+                steps: 11,
+                elapsed: Duration::from_secs(5)
+            })
+        );
+
+        // Counting anew from the stall
+        assert_eq!(monitor.check(22, Duration::from_secs(7)), None);
+        assert!(monitor.check(23, Duration::from_secs(12)).is_some());
+    }
+}
@@ -1,100 +1,62 @@
-/// Demonstrates the simplest way to instantiate the Malachite
-/// core library.
-///
-/// The approach is to simulate everything: the network, signing, mempool, ...
-///
-/// The [`Simulator`] will act as the abstraction that ties together the
-/// execution of the Malachite core library across multiple peers, under
-/// simulated conditions.
-///
-/// Each peer is a simple data structure running in the same thread as all the
-/// other peers.
-/// The [`Simulator`] will orchestrate among different peers and the networking layer.
+/// The simulator binary: a thin command-line interface over the
+/// `malachite_simulator` library, which runs scenarios, replays traces,
+/// checks them, and sweeps over parameters, see `cli.rs`.
 ///
 /// See the top-level README.md for more details.
-use std::process::exit;
-use std::thread;
+use clap::Parser;
+use std::process::ExitCode;
 use tracing::level_filters::LevelFilter;
 use tracing::{error, warn};
 use tracing_subscriber::EnvFilter;
 
-use crate::context::value::BaseValue;
-use crate::simulator::{DecisionsReceiver, ProposalsSender, Simulator};
-
-mod application;
-mod common;
-mod context;
-mod decision;
-mod simulator;
+use malachite_simulator::CancelHandle;
 
-fn main() {
-    // Some sensible defaults to make logging work
-    init();
-
-    // Create a network of 4 peers
-    let (mut n, mut states, proposals, decisions) = Simulator::new(4);
+use crate::cli::{Cli, LogFormat};
 
-    // Spawn a thread that produces values to be proposed
-    produce_proposals_background(proposals);
+mod cli;
 
-    // Spawn a thread in the background that handles decided values
-    consume_decisions_background(decisions);
+fn main() -> ExitCode {
+    let cli = Cli::parse();
 
-    // Run the system
-    // Blocking method, starts the network and handles orchestration of
-    // block building
-    n.run(&mut states);
-
-    // Todo: Clean stop
-}
-
-fn produce_proposals_background(proposals: ProposalsSender) {
-    let mut counter = 45;
-
-    thread::spawn(move || loop {
-        proposals
-            .send(BaseValue(counter))
-            .expect("could not send new value to be proposed");
-        warn!(value = %counter, "IN -> new value to be proposed");
-
-        counter += 1;
-    });
-}
-
-fn consume_decisions_background(rx: DecisionsReceiver) {
-    thread::spawn(move || {
-        // Busy loop, simply consume the decided heights
-        loop {
-            let res = rx.recv();
-            match res {
-                Ok(d) => {
-                    warn!(
-                        peer = %d.peer.to_string(),
-                        value = %d.value_id.to_string(),
-                        height = %d.height,
-                        "OUT <- new decision took place",
-                    );
-                }
-                Err(err) => {
-                    error!(error = ?err, "error receiving decisions");
-                    error!("stopping");
-                    exit(0);
-                }
-            }
+    // Some sensible defaults to make logging work
+    init(cli.log_format, cli.log_level);
+
+    // Ctrl-C stops the simulation before its next step, and it still reports
+    let cancel = CancelHandle::default();
+    let handle = cancel.clone();
+    if let Err(e) = ctrlc::set_handler(move || handle.cancel()) {
+        warn!(error = %e, "cannot handle Ctrl-C");
+    }
+
+    // Run, replay, check or sweep, see `cli.rs`
+    match cli.command.execute(&cancel) {
+        Ok(code) => code,
+        Err(e) => {
+            error!(error = %e, "cannot execute the command");
+            ExitCode::FAILURE
         }
-    });
+    }
 }
 
-fn init() {
-    let filter = EnvFilter::builder()
+fn init(format: LogFormat, level: LevelFilter) {
+    let mut filter = EnvFilter::builder()
         .with_default_directive(LevelFilter::WARN.into())
         .from_env()
-        .unwrap()
-        .add_directive("malachite_simulator=trace".parse().unwrap());
+        .unwrap();
+
+    // Unless `RUST_LOG` says otherwise, only the simulator logs below warnings
+    if std::env::var_os(EnvFilter::DEFAULT_ENV).is_none() {
+        filter = filter.add_directive(format!("malachite_simulator={level}").parse().unwrap());
+    }
 
-    tracing_subscriber::fmt()
+    let subscriber = tracing_subscriber::fmt()
         .with_env_filter(filter)
-        .compact()
-        .with_target(false)
-        .init();
+        .with_target(false);
+
+    match format {
+        LogFormat::Full => subscriber.init(),
+        LogFormat::Compact => subscriber.compact().init(),
+        LogFormat::Pretty => subscriber.pretty().init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
 }
@@ -0,0 +1,19 @@
+/// Observers of simulations, which the simulator notifies as things happen,
+/// e.g., to collect statistics without polling the simulator between steps.
+///
+use crate::decision::Decision;
+use crate::invariants::ViolationReport;
+use crate::liveness::StallReport;
+
+/// Every method does nothing by default, so that an observer only
+/// implements what it is interested in.
+pub trait Observer {
+    /// A peer decided, and the simulator checked the decision.
+    fn on_decision(&mut self, _decision: &Decision) {}
+
+    /// A decision violated safety.
+    fn on_violation(&mut self, _report: &ViolationReport) {}
+
+    /// Consensus stalled, see [`crate::liveness::StallLimits`].
+    fn on_stall(&mut self, _report: &StallReport) {}
+}
@@ -0,0 +1,125 @@
+/// Network partitions, which cut the peers into groups that cannot reach
+/// one another, and schedules to change or heal them over time.
+///
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::context::address::BasePeerAddress;
+
+/// A cut of the peers into groups: envelopes only travel between peers
+/// of the same group.
+///
+/// Peers which appear in none of the groups form one more group together.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Partition {
+    groups: Vec<HashSet<BasePeerAddress>>,
+}
+
+impl Partition {
+    /// For instance, `Partition::new(vec![vec![0, 1], vec![2, 3]])` is
+    /// the partition {0, 1} | {2, 3}.
+    pub fn new(groups: Vec<Vec<u32>>) -> Self {
+        Self {
+            groups: groups
+                .into_iter()
+                .map(|g| g.into_iter().map(BasePeerAddress).collect())
+                .collect(),
+        }
+    }
+
+    /// The peers of each group, in increasing order.
+    pub fn groups(&self) -> Vec<Vec<u32>> {
+        self.groups
+            .iter()
+            .map(|g| {
+                let mut peers: Vec<_> = g.iter().map(|p| p.0).collect();
+                peers.sort();
+                peers
+            })
+            .collect()
+    }
+
+    fn group_of(&self, peer: &BasePeerAddress) -> Option<usize> {
+        self.groups.iter().position(|g| g.contains(peer))
+    }
+
+    /// Whether the partition prevents envelopes from travelling from `a` to `b`.
+    pub fn separates(&self, a: &BasePeerAddress, b: &BasePeerAddress) -> bool {
+        self.group_of(a) != self.group_of(b)
+    }
+}
+
+/// What happens to the envelopes which a partition withholds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CutPolicy {
+    /// The envelopes are lost.
+    #[default]
+    Drop,
+
+    /// The envelopes arrive as soon as the partition no longer
+    /// separates their source from their destination.
+    Buffer,
+}
+
+/// The moment at which a [`PartitionSchedule`] changes the partition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// Before the simulator takes the given step, counting from 0.
+    Step(u64),
+
+    /// At the given simulated time.
+    Time(Duration),
+}
+
+/// The partitions in place over the course of a simulation.
+///
+/// Initially, no partition is in place. Then, at each trigger, the
+/// given partition replaces the current one, or nothing if healing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PartitionSchedule {
+    pub policy: CutPolicy,
+    pub changes: Vec<(Trigger, Option<Partition>)>,
+}
+
+impl PartitionSchedule {
+    pub fn new(policy: CutPolicy) -> Self {
+        Self {
+            policy,
+            changes: vec![],
+        }
+    }
+
+    /// Puts `partition` in place at `trigger`.
+    pub fn split(mut self, trigger: Trigger, partition: Partition) -> Self {
+        self.changes.push((trigger, Some(partition)));
+        self
+    }
+
+    /// Heals any partition at `trigger`.
+    pub fn heal(mut self, trigger: Trigger) -> Self {
+        self.changes.push((trigger, None));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::context::address::BasePeerAddress;
+    use crate::partition::Partition;
+
+    #[test]
+    fn partition_separates_groups() {
+        let p = Partition::new(vec![vec![0, 1], vec![2]]);
+        let peer = BasePeerAddress;
+
+        assert!(!p.separates(&peer(0), &peer(1)));
+        assert!(p.separates(&peer(1), &peer(2)));
+
+        // Peers 3 and 4 are in none of the groups, so together
+        assert!(!p.separates(&peer(3), &peer(4)));
+        assert!(p.separates(&peer(2), &peer(3)));
+
+        // No group at all is no partition
+        assert!(!Partition::default().separates(&peer(0), &peer(3)));
+    }
+}
@@ -0,0 +1,129 @@
+/// When [`Simulator::run`](crate::simulator::Simulator::run) stops, and
+/// what it reports once it does.
+///
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::context::address::BasePeerAddress;
+use crate::context::height::BaseHeight;
+use crate::decision::Decision;
+use crate::invariants::ViolationReport;
+use crate::liveness::StallReport;
+
+/// Stops a running simulation from another thread, e.g., upon Ctrl-C.
+/// The simulation stops before its next step.
+#[derive(Clone, Debug, Default)]
+pub struct CancelHandle(Arc<AtomicBool>);
+
+impl CancelHandle {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// When to stop a simulation: as soon as any of the conditions holds.
+/// Regardless of them, a simulation stops once consensus stalls,
+/// see [`crate::liveness::StallLimits`].
+#[derive(Clone, Debug, Default)]
+pub struct StopConditions {
+    /// Every correct peer decided this height. Byzantine and crashed
+    /// peers do not count.
+    pub height: Option<BaseHeight>,
+
+    /// The simulator took this many steps.
+    pub steps: Option<u64>,
+
+    /// This much simulated time elapsed.
+    pub time: Option<Duration>,
+
+    /// Some decision violated safety, see [`crate::invariants::SafetyChecker`].
+    pub violation: bool,
+
+    pub cancel: Option<CancelHandle>,
+}
+
+/// Which condition stopped the simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    Height,
+    Steps,
+    Time,
+    Violation,
+    Cancelled,
+    /// Consensus stalled, see [`SimulationReport::stall`].
+    Stalled,
+    /// The trace being replayed has no delivery left.
+    EndOfTrace,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Height => write!(f, "target height reached"),
+            StopReason::Steps => write!(f, "step limit reached"),
+            StopReason::Time => write!(f, "time limit reached"),
+            StopReason::Violation => write!(f, "safety violated"),
+            StopReason::Cancelled => write!(f, "cancelled"),
+            StopReason::Stalled => write!(f, "consensus stalled"),
+            StopReason::EndOfTrace => write!(f, "end of the trace"),
+        }
+    }
+}
+
+/// What happened over the course of a simulation.
+#[derive(Clone, Debug)]
+pub struct SimulationReport {
+    pub reason: StopReason,
+    pub steps: u64,
+    pub time: Duration,
+    /// Every decision, in the order they took place.
+    pub decisions: Vec<Decision>,
+    /// The highest height each peer decided, if any.
+    pub heights: BTreeMap<BasePeerAddress, BaseHeight>,
+    pub violations: Vec<ViolationReport>,
+    pub stall: Option<StallReport>,
+}
+
+impl SimulationReport {
+    /// Whether the simulation went as it should: safely, and without stalling.
+    pub fn is_success(&self) -> bool {
+        self.violations.is_empty() && self.stall.is_none()
+    }
+}
+
+impl fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "stopped after {} steps, {:?} of simulated time: {}",
+            self.steps, self.time, self.reason
+        )?;
+        for (peer, height) in self.heights.iter() {
+            writeln!(f, "{peer} decided up to height {height}")?;
+        }
+
+        match self.violations.len() {
+            0 => writeln!(f, "no safety violation")?,
+            n => writeln!(f, "{n} safety violations")?,
+        }
+        for report in self.violations.iter() {
+            writeln!(
+                f,
+                "  at step {} ({:?}): {}",
+                report.step, report.time, report.violation
+            )?;
+        }
+
+        if let Some(stall) = &self.stall {
+            writeln!(f, "{stall}")?;
+        }
+        Ok(())
+    }
+}
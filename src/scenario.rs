@@ -0,0 +1,721 @@
+/// Scenarios: experiments described in a TOML file, which the simulator
+/// runs to completion before summing up what happened.
+///
+/// Every field has a default, except for the number of peers and the stop
+/// condition. Durations are in milliseconds. For instance:
+///
+/// ```toml
+/// peers = 4
+/// seed = 7
+/// proposer = "round-robin"   # or "fixed", "weighted-priority", { seeded-random = 3 }
+/// voting_powers = [1, 1, 1, 1]
+/// mock_signatures = false
+///
+/// [consensus]
+/// quorum = [2, 3]            # more than 2/3 of the voting power
+/// honest = [1, 3]
+/// value_payload = "proposal-only"  # or "parts-only", "proposal-and-parts"
+///
+/// [timeouts]
+/// propose_ms = 3000
+/// prevote_ms = 1000
+///
+/// [network]
+/// latency = { kind = "uniform", min_ms = 50, max_ms = 300 }
+/// drop = 0.05
+/// duplicate = 0.1
+/// reorder = 0.2
+/// reorder_delay_ms = 400
+///
+/// [[network.links]]          # faults of a single link, on top of the above
+/// from = 0
+/// to = 1
+/// drop = 0.5
+///
+/// [[validator_changes]]     # from height 5 onward, only peers 0 to 2 validate
+/// height = 5
+/// peers = [0, 1, 2]
+/// voting_powers = [2, 1, 1]  # by default, the voting powers above
+///
+/// [[byzantine]]
+/// peer = 3
+/// double_prevote = true
+///
+/// [workload]
+/// kind = "sequential"        # or "random"
+/// start = 45
+///
+/// [stop]                     # whichever comes first
+/// heights = 10
+/// steps = 100000
+/// time_ms = 600000
+/// stall_ms = 60000           # no decision for that long
+/// violation = true           # safety is violated
+/// ```
+///
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use malachite_core_consensus::ValuePayload;
+use malachite_core_types::{ThresholdParam, ThresholdParams};
+use serde::Deserialize;
+
+use crate::builder::SimulatorBuilder;
+use crate::byzantine::ByzantineBehaviour;
+use crate::context::address::BasePeerAddress;
+use crate::context::height::BaseHeight;
+use crate::context::peer::BASE_VOTING_POWER;
+use crate::context::proposer::ProposerSelection;
+use crate::context::BaseContext;
+use crate::liveness::StallLimits;
+use crate::report::{SimulationReport, StopConditions};
+use crate::simulator::{ConsensusParams, Latency, LinkFaults, Simulator, States, Workload};
+use crate::timers::TimeoutDurations;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub peers: u32,
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub proposer: ProposerConfig,
+    /// The voting power of each peer, by index. By default, 1 for every peer.
+    pub voting_powers: Option<Vec<u64>>,
+    /// Changes of the validator set, from some height onward.
+    #[serde(default)]
+    pub validator_changes: Vec<ValidatorChangeConfig>,
+    #[serde(default)]
+    pub mock_signatures: bool,
+    #[serde(default)]
+    pub consensus: ConsensusConfig,
+    #[serde(default)]
+    pub timeouts: TimeoutsConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub byzantine: Vec<ByzantineConfig>,
+    #[serde(default)]
+    pub workload: WorkloadConfig,
+    pub stop: StopConfig,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProposerConfig {
+    #[default]
+    Fixed,
+    RoundRobin,
+    WeightedPriority,
+    SeededRandom(u64),
+}
+
+/// Overrides the default parameters of consensus, see [`ConsensusParams`].
+/// Each threshold is a fraction of the voting power, as a numerator and
+/// a denominator, which the voting power must exceed.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConsensusConfig {
+    pub quorum: Option<[u64; 2]>,
+    pub honest: Option<[u64; 2]>,
+    pub value_payload: Option<ValuePayloadConfig>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ValuePayloadConfig {
+    ProposalOnly,
+    PartsOnly,
+    ProposalAndParts,
+}
+
+/// Overrides the default duration of each kind of timeout,
+/// see [`TimeoutDurations`].
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimeoutsConfig {
+    pub propose_ms: Option<u64>,
+    pub propose_delta_ms: Option<u64>,
+    pub prevote_ms: Option<u64>,
+    pub prevote_delta_ms: Option<u64>,
+    pub precommit_ms: Option<u64>,
+    pub precommit_delta_ms: Option<u64>,
+    pub commit_ms: Option<u64>,
+    pub step_limit_ms: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum LatencyConfig {
+    Fixed { ms: u64 },
+    Uniform { min_ms: u64, max_ms: u64 },
+    Exponential { min_ms: u64, mean_ms: u64 },
+}
+
+/// Faults of a link, each overriding the faults it applies on top of.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FaultsConfig {
+    pub latency: Option<LatencyConfig>,
+    pub drop: Option<f64>,
+    pub duplicate: Option<f64>,
+    pub reorder: Option<f64>,
+    pub reorder_delay_ms: Option<u64>,
+}
+
+/// The faults of the link from a peer to another, which sit next to the
+/// peers in the table of the link.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(from = "LinkFields")]
+pub struct LinkConfig {
+    pub from: u32,
+    pub to: u32,
+    pub faults: FaultsConfig,
+}
+
+/// The faults of every link, and of specific links. The faults of every
+/// link sit next to the links in the `[network]` table.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(from = "NetworkFields")]
+pub struct NetworkConfig {
+    pub faults: FaultsConfig,
+    pub links: Vec<LinkConfig>,
+}
+
+// The table of a link as it is written. Flattening the faults into it
+// would let serde accept unknown fields, so they are listed here.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LinkFields {
+    from: u32,
+    to: u32,
+    latency: Option<LatencyConfig>,
+    drop: Option<f64>,
+    duplicate: Option<f64>,
+    reorder: Option<f64>,
+    reorder_delay_ms: Option<u64>,
+}
+
+impl From<LinkFields> for LinkConfig {
+    fn from(l: LinkFields) -> Self {
+        LinkConfig {
+            from: l.from,
+            to: l.to,
+            faults: FaultsConfig {
+                latency: l.latency,
+                drop: l.drop,
+                duplicate: l.duplicate,
+                reorder: l.reorder,
+                reorder_delay_ms: l.reorder_delay_ms,
+            },
+        }
+    }
+}
+
+// The `[network]` table as it is written, see `LinkFields`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NetworkFields {
+    latency: Option<LatencyConfig>,
+    drop: Option<f64>,
+    duplicate: Option<f64>,
+    reorder: Option<f64>,
+    reorder_delay_ms: Option<u64>,
+    #[serde(default)]
+    links: Vec<LinkConfig>,
+}
+
+impl From<NetworkFields> for NetworkConfig {
+    fn from(n: NetworkFields) -> Self {
+        NetworkConfig {
+            faults: FaultsConfig {
+                latency: n.latency,
+                drop: n.drop,
+                duplicate: n.duplicate,
+                reorder: n.reorder,
+                reorder_delay_ms: n.reorder_delay_ms,
+            },
+            links: n.links,
+        }
+    }
+}
+
+/// The validators from a height onward, until the next change.
+/// Heights count from 0, as the heights of decisions do.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ValidatorChangeConfig {
+    pub height: u64,
+    pub peers: Vec<u32>,
+    /// The voting power of each of the peers, in the same order. By default,
+    /// the voting power each peer starts with.
+    pub voting_powers: Option<Vec<u64>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ByzantineConfig {
+    pub peer: u32,
+    #[serde(default)]
+    pub equivocate_proposals: bool,
+    #[serde(default)]
+    pub double_prevote: bool,
+    #[serde(default)]
+    pub double_precommit: bool,
+    #[serde(default)]
+    pub vote_for_unknown_values: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum WorkloadConfig {
+    Sequential { start: u64 },
+    Random,
+}
+
+impl Default for WorkloadConfig {
+    fn default() -> Self {
+        WorkloadConfig::Sequential { start: 45 }
+    }
+}
+
+/// When the scenario ends: as soon as any of the conditions holds.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StopConfig {
+    /// Every correct peer decided this many heights.
+    pub heights: Option<u64>,
+    pub steps: Option<u64>,
+    /// This much simulated time elapsed.
+    pub time_ms: Option<u64>,
+    /// No peer decided for this much simulated time, see [`StallLimits`].
+    pub stall_ms: Option<u64>,
+    /// Some decision violated safety.
+    #[serde(default)]
+    pub violation: bool,
+}
+
+#[derive(Debug)]
+pub enum ScenarioError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Io(e) => write!(f, "{e}"),
+            ScenarioError::Parse(e) => write!(f, "{e}"),
+            ScenarioError::Invalid(reason) => write!(f, "invalid scenario: {reason}"),
+        }
+    }
+}
+
+/// Four peers, deciding for as long as consensus does not stall.
+impl Default for Scenario {
+    fn default() -> Self {
+        Scenario {
+            peers: 4,
+            seed: 0,
+            proposer: ProposerConfig::default(),
+            voting_powers: None,
+            validator_changes: vec![],
+            mock_signatures: false,
+            consensus: ConsensusConfig::default(),
+            timeouts: TimeoutsConfig::default(),
+            network: NetworkConfig::default(),
+            byzantine: vec![],
+            workload: WorkloadConfig::default(),
+            stop: StopConfig {
+                stall_ms: Some(60_000),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Scenario {
+    pub fn load(path: impl AsRef<Path>) -> Result<Scenario, ScenarioError> {
+        fs::read_to_string(path).map_err(ScenarioError::Io)?.parse()
+    }
+
+    /// Checks that the scenario makes sense, as loading it does.
+    pub fn validate(&self) -> Result<(), ScenarioError> {
+        let invalid = |reason: String| Err(ScenarioError::Invalid(reason));
+
+        if self.peers == 0 {
+            return invalid("no peer".to_string());
+        }
+        if let Some(powers) = &self.voting_powers {
+            if powers.len() != self.peers as usize {
+                return invalid(format!(
+                    "{} voting powers for {} peers",
+                    powers.len(),
+                    self.peers
+                ));
+            }
+            if powers.iter().all(|p| *p == 0) {
+                return invalid("no peer has any voting power".to_string());
+            }
+        }
+
+        for [numerator, denominator] in [self.consensus.quorum, self.consensus.honest]
+            .into_iter()
+            .flatten()
+        {
+            if numerator >= denominator {
+                return invalid(format!(
+                    "threshold {numerator}/{denominator}, expected less than 1"
+                ));
+            }
+        }
+
+        for change in self.validator_changes.iter() {
+            let height = change.height;
+            if change.peers.is_empty() {
+                return invalid(format!("no validator from height {height}"));
+            }
+            if let Some(powers) = &change.voting_powers {
+                if powers.len() != change.peers.len() {
+                    return invalid(format!(
+                        "{} voting powers for {} validators from height {height}",
+                        powers.len(),
+                        change.peers.len()
+                    ));
+                }
+                if powers.iter().all(|p| *p == 0) {
+                    return invalid(format!("no voting power from height {height}"));
+                }
+            }
+            // Without voting powers of their own, the validators keep theirs
+            if let (None, Some(initial)) = (&change.voting_powers, &self.voting_powers) {
+                let power = |p: &u32| initial.get(*p as usize).copied();
+                if change.peers.iter().all(|p| power(p) == Some(0)) {
+                    return invalid(format!("no voting power from height {height}"));
+                }
+            }
+            let mut peers = change.peers.clone();
+            peers.sort();
+            peers.dedup();
+            if peers.len() != change.peers.len() {
+                return invalid(format!("repeated validator from height {height}"));
+            }
+        }
+
+        if let Err(reason) = self.network.faults.check() {
+            return invalid(format!("{reason} on the network"));
+        }
+        for link in self.network.links.iter() {
+            if let Err(reason) = link.faults.check() {
+                return invalid(format!(
+                    "{reason} on the link from {} to {}",
+                    link.from, link.to
+                ));
+            }
+        }
+
+        let peers = self.byzantine.iter().map(|b| b.peer);
+        let links = self.network.links.iter().flat_map(|l| [l.from, l.to]);
+        let validators = self.validator_changes.iter().flat_map(|c| c.peers.clone());
+        if let Some(peer) = peers
+            .chain(links)
+            .chain(validators)
+            .find(|p| *p >= self.peers)
+        {
+            return invalid(format!("no peer {peer}"));
+        }
+
+        let stop = &self.stop;
+        if stop.heights == Some(0) {
+            return invalid("the stop condition needs at least one height".to_string());
+        }
+        if [stop.heights, stop.steps, stop.time_ms, stop.stall_ms]
+            .iter()
+            .all(Option::is_none)
+        {
+            return invalid(
+                "the stop condition needs heights, steps, time_ms or stall_ms".to_string(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Configures a simulator as the scenario describes it, leaving room
+    /// for more, e.g., observers. Fails if the scenario is not valid.
+    pub fn builder(&self) -> Result<SimulatorBuilder, ScenarioError> {
+        self.validate()?;
+
+        let proposer_selection = match self.proposer {
+            ProposerConfig::Fixed => ProposerSelection::Fixed,
+            ProposerConfig::RoundRobin => ProposerSelection::RoundRobin,
+            ProposerConfig::WeightedPriority => ProposerSelection::WeightedPriority,
+            ProposerConfig::SeededRandom(seed) => ProposerSelection::SeededRandom(seed),
+        };
+        let default = self.network.faults.apply(LinkFaults::default());
+
+        let mut builder = SimulatorBuilder::new(self.peers)
+            .seed(self.seed)
+            .context(BaseContext::with_proposer_selection(proposer_selection))
+            .consensus_params(self.consensus.params())
+            .timeouts(self.timeouts.durations())
+            .mock_signatures(self.mock_signatures)
+            .faults(default)
+            .workload(match self.workload {
+                WorkloadConfig::Sequential { start } => Workload::Sequential(start),
+                WorkloadConfig::Random => Workload::Random,
+            })
+            .stall_limits(StallLimits {
+                steps: None,
+                time: self.stop.stall_ms.map(Duration::from_millis),
+            });
+
+        if let Some(powers) = &self.voting_powers {
+            builder = builder.voting_powers(powers.clone());
+        }
+
+        for change in self.validator_changes.iter() {
+            let validators = change
+                .peers
+                .iter()
+                .enumerate()
+                .map(|(i, peer)| {
+                    let power = match (&change.voting_powers, &self.voting_powers) {
+                        (Some(powers), _) => powers[i],
+                        (None, Some(powers)) => powers[*peer as usize],
+                        (None, None) => BASE_VOTING_POWER,
+                    };
+                    (BasePeerAddress(*peer), power)
+                })
+                .collect();
+            builder = builder.validators_from(BaseHeight(change.height), validators);
+        }
+
+        for link in self.network.links.iter() {
+            let faults = link.faults.apply(default);
+            builder = builder.link(BasePeerAddress(link.from), BasePeerAddress(link.to), faults);
+        }
+
+        for b in self.byzantine.iter() {
+            let behaviour = ByzantineBehaviour {
+                equivocate_proposals: b.equivocate_proposals,
+                double_prevote: b.double_prevote,
+                double_precommit: b.double_precommit,
+                vote_for_unknown_values: b.vote_for_unknown_values,
+            };
+            builder = builder.byzantine(BasePeerAddress(b.peer), behaviour);
+        }
+
+        Ok(builder)
+    }
+
+    /// Sets up the simulator as the scenario describes it.
+    /// Fails if the scenario is not valid.
+    pub fn build(&self) -> Result<(Simulator, States), ScenarioError> {
+        let (n, states, _proposals, _decisions) = self.builder()?.build();
+        Ok((n, states))
+    }
+
+    /// When to stop the simulation, as per the scenario.
+    pub fn stop_conditions(&self) -> StopConditions {
+        StopConditions {
+            height: self.stop.heights.map(|h| BaseHeight(h.saturating_sub(1))),
+            steps: self.stop.steps,
+            time: self.stop.time_ms.map(Duration::from_millis),
+            violation: self.stop.violation,
+            ..Default::default()
+        }
+    }
+
+    /// Runs the scenario until its stop condition holds, or until the
+    /// simulation stalls. Fails if the scenario is not valid.
+    pub fn run(&self) -> Result<SimulationReport, ScenarioError> {
+        let (mut n, mut states) = self.build()?;
+        Ok(n.run(&mut states, self.stop_conditions()))
+    }
+}
+
+impl std::str::FromStr for Scenario {
+    type Err = ScenarioError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let scenario: Scenario = toml::from_str(s).map_err(ScenarioError::Parse)?;
+        scenario.validate()?;
+
+        Ok(scenario)
+    }
+}
+
+impl ConsensusConfig {
+    fn params(&self) -> ConsensusParams {
+        let d = ConsensusParams::default();
+        let threshold = |v: Option<[u64; 2]>, default| {
+            v.map(|[numerator, denominator]| ThresholdParam::new(numerator, denominator))
+                .unwrap_or(default)
+        };
+
+        ConsensusParams {
+            threshold_params: ThresholdParams {
+                quorum: threshold(self.quorum, d.threshold_params.quorum),
+                honest: threshold(self.honest, d.threshold_params.honest),
+            },
+            value_payload: match self.value_payload {
+                None => d.value_payload,
+                Some(ValuePayloadConfig::ProposalOnly) => ValuePayload::ProposalOnly,
+                Some(ValuePayloadConfig::PartsOnly) => ValuePayload::PartsOnly,
+                Some(ValuePayloadConfig::ProposalAndParts) => ValuePayload::ProposalAndParts,
+            },
+        }
+    }
+}
+
+impl TimeoutsConfig {
+    fn durations(&self) -> TimeoutDurations {
+        let d = TimeoutDurations::default();
+        let ms = |v: Option<u64>, default| v.map(Duration::from_millis).unwrap_or(default);
+
+        TimeoutDurations {
+            propose: ms(self.propose_ms, d.propose),
+            propose_delta: ms(self.propose_delta_ms, d.propose_delta),
+            prevote: ms(self.prevote_ms, d.prevote),
+            prevote_delta: ms(self.prevote_delta_ms, d.prevote_delta),
+            precommit: ms(self.precommit_ms, d.precommit),
+            precommit_delta: ms(self.precommit_delta_ms, d.precommit_delta),
+            commit: ms(self.commit_ms, d.commit),
+            step_limit: ms(self.step_limit_ms, d.step_limit),
+        }
+    }
+}
+
+impl FaultsConfig {
+    // The faults as given, on top of the defaults, see [`LinkFaults::check`].
+    fn check(&self) -> Result<(), String> {
+        self.apply(LinkFaults::default()).check()
+    }
+
+    fn apply(&self, base: LinkFaults) -> LinkFaults {
+        LinkFaults {
+            latency: match self.latency {
+                None => base.latency,
+                Some(LatencyConfig::Fixed { ms }) => Latency::Fixed(Duration::from_millis(ms)),
+                Some(LatencyConfig::Uniform { min_ms, max_ms }) => Latency::Uniform {
+                    min: Duration::from_millis(min_ms),
+                    max: Duration::from_millis(max_ms),
+                },
+                Some(LatencyConfig::Exponential { min_ms, mean_ms }) => Latency::Exponential {
+                    min: Duration::from_millis(min_ms),
+                    mean: Duration::from_millis(mean_ms),
+                },
+            },
+            drop: self.drop.unwrap_or(base.drop),
+            duplicate: self.duplicate.unwrap_or(base.duplicate),
+            reorder: self.reorder.unwrap_or(base.reorder),
+            reorder_delay: self
+                .reorder_delay_ms
+                .map(Duration::from_millis)
+                .unwrap_or(base.reorder_delay),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::context::address::BasePeerAddress;
+    use crate::context::height::BaseHeight;
+    use crate::report::StopReason;
+    use crate::scenario::{Scenario, ScenarioError};
+
+    #[test]
+    fn scenario_runs_to_its_stop_condition() {
+        let scenario: Scenario = r#"
+            peers = 4
+            seed = 7
+            proposer = "round-robin"
+            mock_signatures = true
+
+            [network]
+            latency = { kind = "uniform", min_ms = 50, max_ms = 300 }
+            duplicate = 0.1
+            reorder = 0.2
+
+            [[network.links]]
+            from = 0
+            to = 1
+            latency = { kind = "fixed", ms = 500 }
+
+            [[byzantine]]
+            peer = 3
+            double_prevote = true
+
+            [workload]
+            kind = "random"
+
+            [stop]
+            heights = 3
+            time_ms = 600000
+        "#
+        .parse()
+        .unwrap();
+
+        let report = scenario.run().unwrap();
+        assert_eq!(report.reason, StopReason::Height);
+        for peer in 0..3 {
+            assert!(report.heights[&BasePeerAddress(peer)] >= BaseHeight(2));
+        }
+        assert!(report.violations.is_empty());
+        assert!(report.stall.is_none());
+
+        // Typos and impossible scenarios are rejected
+        assert!(matches!(
+            "peers = 4\n[stop]\nheight = 3".parse::<Scenario>(),
+            Err(ScenarioError::Parse(_))
+        ));
+        assert!(matches!(
+            "peers = 4\n[network]\ndorp = 0.2\n[stop]\nheights = 3".parse::<Scenario>(),
+            Err(ScenarioError::Parse(_))
+        ));
+        assert!(matches!(
+            "peers = 4\n[[network.links]]\nfrom = 0\nto = 1\ndorp = 0.2\n[stop]\nheights = 3"
+                .parse::<Scenario>(),
+            Err(ScenarioError::Parse(_))
+        ));
+        assert!(matches!(
+            "peers = 4\n[[byzantine]]\npeer = 4\n[stop]\nheights = 3".parse::<Scenario>(),
+            Err(ScenarioError::Invalid(_))
+        ));
+        assert!(matches!(
+            "peers = 4\n[consensus]\nquorum = [3, 3]\n[stop]\nheights = 3".parse::<Scenario>(),
+            Err(ScenarioError::Invalid(_))
+        ));
+        assert!(matches!(
+            "peers = 4\n[network]\ndrop = 1.5\n[stop]\nheights = 3".parse::<Scenario>(),
+            Err(ScenarioError::Invalid(_))
+        ));
+        assert!(matches!(
+            "peers = 4\n[[network.links]]\nfrom = 0\nto = 1\nreorder = -0.1\n[stop]\nheights = 3"
+                .parse::<Scenario>(),
+            Err(ScenarioError::Invalid(_))
+        ));
+        assert!(matches!(
+            "peers = 4\n[network]\nlatency = { kind = \"uniform\", min_ms = 300, max_ms = 50 }\n\
+             [stop]\nheights = 3"
+                .parse::<Scenario>(),
+            Err(ScenarioError::Invalid(_))
+        ));
+        assert!(matches!(
+            "peers = 2\nvoting_powers = [1, 0]\n[[validator_changes]]\nheight = 1\npeers = [1]\n\
+             [stop]\nheights = 3"
+                .parse::<Scenario>(),
+            Err(ScenarioError::Invalid(_))
+        ));
+
+        // Scenarios set up in code are checked as well
+        let mut scenario = Scenario::default();
+        scenario.stop.heights = Some(0);
+        assert_eq!(scenario.stop_conditions().height, Some(BaseHeight(0)));
+        assert!(matches!(scenario.run(), Err(ScenarioError::Invalid(_))));
+    }
+}
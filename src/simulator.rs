@@ -1,25 +1,45 @@
 use crossbeam_channel as cbc;
-use std::collections::HashMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
-use std::thread;
 use std::time::Duration;
-use tracing::{debug, error, info, span, trace, Level};
+use tracing::{debug, error, info, span, trace, warn, Level};
 
 use malachite_core_consensus::{Error, Input, Params, State, ValuePayload};
+use malachite_core_types::{
+    CommitCertificate, SignedProposalPart, ThresholdParams, Value, VotingPower,
+};
 use malachite_metrics::Metrics;
 
 use crate::application::Application;
+use crate::byzantine::{ByzantineBehaviour, UNKNOWN_VALUE};
+use crate::clock::{Event, EventQueue, VirtualClock};
 use crate::common;
 use crate::context::address::BasePeerAddress;
 use crate::context::height::BaseHeight;
+use crate::context::peer::BASE_VOTING_POWER;
 use crate::context::peer_set::BasePeerSet;
+use crate::context::proposer::ProposerSelection;
+use crate::context::signing_provider::BaseSigningProvider;
 use crate::context::value::BaseValue;
 use crate::context::BaseContext;
 use crate::decision::Decision;
+use crate::invariants::{SafetyChecker, ViolationReport};
+use crate::liveness::{LivenessMonitor, PeerSnapshot, Stall, StallLimits, StallReport};
+use crate::observer::Observer;
+use crate::partition::{CutPolicy, Partition, PartitionSchedule, Trigger};
+use crate::report::{SimulationReport, StopConditions, StopReason};
+use crate::streaming::PartStreams;
+use crate::timers::{TimeoutDurations, Timers};
+use crate::trace::{Fault, Trace, TraceEntry, TraceEvent, TraceHeader};
+use crate::validators::{self, ValidatorSchedule};
 
-/// The delay between each consecutive step the simulator takes.
-pub const STEP_DELAY: Duration = Duration::from_millis(200);
+/// The simulated time it takes for an envelope to travel from a peer to another,
+/// unless the [`NetworkModel`] says otherwise.
+/// Envelopes which a peer sends to itself arrive instantly.
+pub const NETWORK_DELAY: Duration = Duration::from_millis(200);
 
 /// A stream of [`BaseValue`]s.
 /// Each value is treated as a Proposal to consensus.
@@ -42,27 +62,294 @@ pub type DecisionsReceiver = Receiver<Decision>;
 /// The [`Application`] logic at various peers sent these messages.
 pub type NetSender = Sender<Envelope>;
 
+/// The consensus state of each peer, by address.
+pub type States = BTreeMap<BasePeerAddress, State<BaseContext>>;
+
 /// The receiving side of the networking layer.
-/// The [`Simulator`] takes each message from this queue and applies that
-/// message to the appropriate peer.
+/// The [`Simulator`] takes each message from this queue and schedules its
+/// delivery to the appropriate peer.
 pub type NetReceiver = Receiver<Envelope>;
 
-/// Represents a message with an [`Input`] to the application logic
-/// at a certain peer.
+/// Represents a [`Message`] to the application logic at a certain peer.
 ///
 /// Peers send envelopes to one another, potentially to themselves in the
 /// process of reaching consensus on a decision.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Envelope {
     pub source: BasePeerAddress,
     pub destination: BasePeerAddress,
-    pub payload: Input<BaseContext>,
+    pub payload: Message,
+}
+
+/// What an [`Envelope`] carries.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    /// An [`Input`] for consensus at the destination.
+    Input(Input<BaseContext>),
+
+    /// A part of a value which the source streams. The application at the
+    /// destination reassembles the value, see [`crate::streaming`].
+    ProposalPart(SignedProposalPart<BaseContext>),
+}
+
+/// How long an envelope takes to travel over a link between two peers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Latency {
+    /// Always the same delay.
+    Fixed(Duration),
+
+    /// A delay drawn uniformly between `min` and `max`, both included.
+    /// If `min` exceeds `max`, the delay is always `max`.
+    Uniform { min: Duration, max: Duration },
+
+    /// At least `min`, plus a delay drawn from an exponential distribution
+    /// with the given `mean`, so that a few envelopes are much slower than the others.
+    Exponential { min: Duration, mean: Duration },
+}
+
+impl Latency {
+    fn sample(&self, rng: &mut StdRng) -> Duration {
+        match *self {
+            Latency::Fixed(delay) => delay,
+            Latency::Uniform { min, max } => rng.gen_range(min.min(max)..=max),
+            Latency::Exponential { min, mean } => {
+                // Inverse transform sampling, where `1 - u` is never zero
+                let u: f64 = rng.gen();
+                min + mean.mul_f64(-(1.0 - u).ln())
+            }
+        }
+    }
+}
+
+/// The faults which a link applies to the envelopes travelling over it.
+///
+/// Probabilities range from 0 (never) to 1 (always). The network clamps
+/// those outside this range to the nearest bound and takes a probability
+/// which is not a number as 0, see [`LinkFaults::check`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinkFaults {
+    pub latency: Latency,
+
+    /// The probability that the link loses an envelope.
+    pub drop: f64,
+
+    /// The probability that the link delivers an envelope twice,
+    /// each copy arriving after its own latency.
+    pub duplicate: f64,
+
+    /// The probability that the link holds an envelope back for an
+    /// extra `reorder_delay`, so that later envelopes overtake it.
+    pub reorder: f64,
+    pub reorder_delay: Duration,
+}
+
+impl Default for LinkFaults {
+    fn default() -> Self {
+        Self::reliable(Latency::Fixed(NETWORK_DELAY))
+    }
+}
+
+impl LinkFaults {
+    /// A link which delivers every envelope exactly once, after the given latency.
+    pub fn reliable(latency: Latency) -> Self {
+        Self {
+            latency,
+            drop: 0.0,
+            duplicate: 0.0,
+            reorder: 0.0,
+            reorder_delay: NETWORK_DELAY,
+        }
+    }
+
+    /// Whether every probability ranges from 0 to 1 and a uniform latency
+    /// has its minimum below its maximum. If not, returns the first fault
+    /// which does not, as the network would not apply it as given.
+    pub fn check(&self) -> Result<(), String> {
+        let probabilities = [
+            ("drop", self.drop),
+            ("duplicate", self.duplicate),
+            ("reorder", self.reorder),
+        ];
+        for (name, p) in probabilities {
+            if !(0.0..=1.0).contains(&p) {
+                return Err(format!("{name} probability {p}, expected between 0 and 1"));
+            }
+        }
+
+        if let Latency::Uniform { min, max } = self.latency {
+            if min > max {
+                return Err(format!("uniform latency from {min:?} to {max:?}"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Whether an event of probability `p` happens, as per the rule of [`LinkFaults`].
+fn happens(rng: &mut StdRng, p: f64) -> bool {
+    let p = if p.is_nan() { 0.0 } else { p.clamp(0.0, 1.0) };
+    rng.gen_bool(p)
+}
+
+/// The fault model of the simulated network, i.e., the [`LinkFaults`]
+/// of every directed link from a peer to another.
+///
+/// Envelopes which a peer sends to itself do not travel over any link,
+/// so they always arrive, once, and instantly.
+/// Faults are random, drawn from a generator seeded with the given seed,
+/// so the same seed leads to the same faults.
+pub struct NetworkModel {
+    /// The faults of every link which has none of its own.
+    pub default: LinkFaults,
+
+    /// The faults of specific links, by source and destination.
+    pub links: HashMap<(BasePeerAddress, BasePeerAddress), LinkFaults>,
+
+    rng: StdRng,
+}
+
+impl Default for NetworkModel {
+    fn default() -> Self {
+        Self::new(LinkFaults::default(), 0)
+    }
+}
+
+impl NetworkModel {
+    pub fn new(default: LinkFaults, seed: u64) -> Self {
+        Self {
+            default,
+            links: HashMap::new(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Overrides the faults of the link from `source` to `destination`.
+    pub fn set_link(
+        &mut self,
+        source: BasePeerAddress,
+        destination: BasePeerAddress,
+        faults: LinkFaults,
+    ) {
+        self.links.insert((source, destination), faults);
+    }
+
+    pub fn link(&self, source: BasePeerAddress, destination: BasePeerAddress) -> &LinkFaults {
+        self.links
+            .get(&(source, destination))
+            .unwrap_or(&self.default)
+    }
+
+    /// Decides what happens to an envelope which `source` sends to `destination`.
+    /// Returns the delay after which each copy of the envelope arrives:
+    /// none if the link drops it, two if the link duplicates it.
+    pub fn transmit(
+        &mut self,
+        source: BasePeerAddress,
+        destination: BasePeerAddress,
+    ) -> Vec<Duration> {
+        if source == destination {
+            return vec![Duration::ZERO];
+        }
+
+        let faults = *self.link(source, destination);
+
+        if happens(&mut self.rng, faults.drop) {
+            return vec![];
+        }
+
+        let copies = if happens(&mut self.rng, faults.duplicate) {
+            2
+        } else {
+            1
+        };
+
+        (0..copies)
+            .map(|_| {
+                let mut delay = faults.latency.sample(&mut self.rng);
+                if happens(&mut self.rng, faults.reorder) {
+                    delay += faults.reorder_delay;
+                }
+                delay
+            })
+            .collect()
+    }
+}
+
+/// Whether a peer runs normally, and how it fails otherwise.
+/// Peers can switch between modes at any time, see [`Simulator::set_peer_mode`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PeerMode {
+    #[default]
+    Running,
+
+    /// The peer handles no input and none of its timeouts elapse.
+    /// The envelopes which reach it are lost.
+    /// Switching back to running, the peer resumes where it stopped.
+    Crashed,
+
+    /// The peer handles inputs, but the envelopes it sends to other peers are lost.
+    Silent,
+
+    /// The envelopes which the peer sends to other peers take the given
+    /// extra delay to arrive.
+    Slow(Duration),
+}
+
+/// Where the values which peers propose come from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Workload {
+    /// The environment sends values through the [`ProposalsSender`].
+    #[default]
+    External,
+
+    /// The simulator supplies the values `start`, `start + 1`, and so on.
+    Sequential(u64),
+
+    /// The simulator supplies values drawn from a generator seeded with
+    /// the seed of the simulator.
+    Random,
+}
+
+/// The parameters of consensus which every peer shares.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConsensusParams {
+    /// The share of the voting power which a quorum, and a set of peers
+    /// including at least one correct peer, hold more than.
+    pub threshold_params: ThresholdParams,
+
+    /// How the value of a proposal travels to the other peers: within the
+    /// proposal message, as a stream of parts from the proposer, or both.
+    /// With parts only, consensus never publishes proposals, and each peer
+    /// makes up the proposal from the parts it receives.
+    pub value_payload: ValuePayload,
+}
+
+impl Default for ConsensusParams {
+    fn default() -> Self {
+        Self {
+            threshold_params: ThresholdParams::default(),
+            value_payload: ValuePayload::ProposalOnly,
+        }
+    }
+}
+
+/// A change to the simulated environment, which happens at a scheduled moment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// The given partition replaces the current one, if any.
+    /// No partition means the network heals.
+    Partition(Option<Partition>),
+
+    /// The peer switches to the given mode.
+    PeerMode(BasePeerAddress, PeerMode),
 }
 
 /// A system simulator represents:
 ///
 /// - Some state of peers, namely: params, metrics, and application logic.
 /// - The environment for executing the application and producing decisions: the network
-///     layer, which is simulated in this case.
+///   layer, which is simulated in this case.
 ///
 pub struct Simulator {
     // Params of each peer.
@@ -77,23 +364,206 @@ pub struct Simulator {
     // Simulates the receiver-side of the networking layer.
     // The sender-side of networking is registered in each application.
     network_rx: NetReceiver,
+
+    // The simulated time; timeouts and network delays count according to this clock.
+    clock: VirtualClock,
+
+    // The envelopes in flight and the timeouts to elapse, ordered by simulated time.
+    events: EventQueue,
+
+    // The moment of the earliest `Event::Wake` pending for each peer.
+    wakes: HashMap<BasePeerAddress, Duration>,
+
+    // Decides how long envelopes take to travel between two different peers,
+    // and whether they arrive at all.
+    network: NetworkModel,
+
+    // The partition in place, if any, and what happens to the envelopes it withholds.
+    partition: Option<Partition>,
+    cut_policy: CutPolicy,
+
+    // The envelopes which the partition withholds until it heals, if buffering them.
+    withheld: Vec<Envelope>,
+
+    // The proposals which arrived before their destination reached their height,
+    // see `Simulator::is_early`.
+    early: Vec<Envelope>,
+
+    // The mode of each peer which is not running normally.
+    modes: HashMap<BasePeerAddress, PeerMode>,
+
+    // The changes which happen before a given step, ordered by step.
+    step_changes: Vec<(u64, Change)>,
+
+    // The number of steps taken so far.
+    steps: u64,
+
+    // The decisions which the applications take, before checking them.
+    decisions_rx: DecisionsReceiver,
+
+    // Forwards the decisions to the environment, once checked.
+    decisions_tx: DecisionsSender,
+
+    // Every decision so far, once checked, and the highest height each peer decided.
+    decisions: Vec<Decision>,
+    heights: BTreeMap<BasePeerAddress, BaseHeight>,
+
+    // Checks that decisions are safe, as soon as they take place.
+    checker: SafetyChecker,
+
+    // Detects when consensus stops making progress.
+    liveness: LivenessMonitor,
+
+    // The latest stall, if any.
+    stall: Option<StallReport>,
+
+    // The seed from which all randomness derives.
+    seed: u64,
+
+    // Where the values to propose come from, and how to draw them.
+    workload: Workload,
+    workload_rng: StdRng,
+    proposals_tx: ProposalsSender,
+
+    // The selection of proposers, as the peers have it in their context.
+    proposer_selection: ProposerSelection,
+
+    // Every delivered envelope, fault, change, and decision, if recording.
+    trace: Option<Vec<TraceEntry>>,
+
+    // The deliveries left to replay, if replaying a trace.
+    replay: Option<VecDeque<TraceEntry>>,
+
+    // Notified of every decision, violation and stall, in order.
+    observers: Vec<Box<dyn Observer>>,
 }
 
 impl Simulator {
     /// Creates a new system simulator consisting of `size` number of peers.
-    /// Each peer is a validator in the system.
+    /// Each peer is a validator in the system. All randomness derives from
+    /// the seed 0, see [`Simulator::with_seed`].
+    ///
+    /// Assumes there is at least one peer.
+    pub fn new(size: u32) -> (Simulator, States, ProposalsSender, DecisionsReceiver) {
+        Self::with_context(size, BaseContext::new())
+    }
+
+    /// Creates a new system simulator consisting of `size` number of peers,
+    /// each of them with a copy of the context `ctx`, like
+    /// [`Simulator::with_seed`] with the seed 0.
+    pub fn with_context(
+        size: u32,
+        ctx: BaseContext,
+    ) -> (Simulator, States, ProposalsSender, DecisionsReceiver) {
+        Self::with_seed(size, ctx, 0)
+    }
+
+    /// Creates a new system simulator consisting of `size` number of peers,
+    /// each of them with a copy of the context `ctx`, where all randomness
+    /// derives from `seed`: the signing keys, the faults of the network,
+    /// and the values of the [`Workload`].
     ///
-    /// Assumes the size of the system is >= 4 and < 25.
-    pub fn new(
+    /// Two simulations with the same seed and the same configuration,
+    /// whose environment sends no proposals from another thread, take the
+    /// same steps and record the same trace, see [`Simulator::record_trace`].
+    pub fn with_seed(
         size: u32,
+        ctx: BaseContext,
+        seed: u64,
+    ) -> (Simulator, States, ProposalsSender, DecisionsReceiver) {
+        Self::with_voting_powers(ctx, seed, vec![BASE_VOTING_POWER; size as usize])
+    }
+
+    /// Creates a new system simulator like [`Simulator::with_seed`], with one
+    /// peer for each of the given voting powers, in that order.
+    /// Panics if every voting power is zero.
+    pub fn with_voting_powers(
+        ctx: BaseContext,
+        seed: u64,
+        voting_powers: Vec<VotingPower>,
+    ) -> (Simulator, States, ProposalsSender, DecisionsReceiver) {
+        let signing_providers: Vec<_> = (0..voting_powers.len() as u32)
+            .map(|i| BaseSigningProvider::derive(seed, i))
+            .collect();
+        let val_set = BasePeerSet::with_voting_powers(
+            signing_providers.iter().map(|sp| sp.public_key()).collect(),
+            voting_powers,
+        );
+
+        let (mut n, states, ps, dr) = Self::build(ctx, signing_providers, val_set);
+        n.seed = seed;
+        n.network = NetworkModel::new(LinkFaults::default(), seed);
+        n.workload_rng = StdRng::seed_from_u64(seed);
+
+        (n, states, ps, dr)
+    }
+
+    /// Creates a new system simulator with one peer for each of the given
+    /// signing providers, in that order.
+    /// Each peer has a copy of the context `ctx`, holding its own signing provider.
+    ///
+    /// Assumes there is at least one peer.
+    pub fn with_signing_providers(
+        ctx: BaseContext,
+        signing_providers: Vec<BaseSigningProvider>,
     ) -> (
         Simulator,
-        Vec<State<BaseContext>>, // The consensus state of peers
-        ProposalsSender,         // Send proposals (inputs to the system)
-        DecisionsReceiver,       // Receive decisions (outputs of the system)
+        States,            // The consensus state of peers
+        ProposalsSender,   // Send proposals (inputs to the system)
+        DecisionsReceiver, // Receive decisions (outputs of the system)
     ) {
-        assert!(size >= 4);
-        assert!(size < 25);
+        let val_set =
+            BasePeerSet::new(signing_providers.iter().map(|sp| sp.public_key()).collect());
+
+        Self::build(ctx, signing_providers, val_set)
+    }
+
+    /// Creates a new system simulator which replays `trace`, rather than
+    /// simulating a network: each step delivers the next envelope of the trace
+    /// to its destination, at the step and time of the trace, and the envelopes
+    /// which peers send are discarded.
+    /// Since the peers receive the same inputs, in the same order, they take
+    /// the same decisions as in the simulation which recorded the trace.
+    ///
+    /// The peers verify signatures against the public keys in the trace.
+    /// They sign with keys derived from the seed in the trace, which are the
+    /// keys they signed with originally if the simulator had a seed,
+    /// see [`Simulator::with_seed`].
+    pub fn replay(trace: &Trace) -> (Simulator, States, DecisionsReceiver) {
+        let header = &trace.header;
+        let ctx = BaseContext::with_proposer_selection(header.proposer_selection);
+        let signing_providers = (0..header.validators.peers().len() as u32)
+            .map(|i| BaseSigningProvider::derive(header.seed, i))
+            .collect();
+
+        let (mut n, mut states, _, dr) =
+            Self::build(ctx, signing_providers, header.validators.clone());
+        n.seed = header.seed;
+        n.set_consensus_params(&mut states, header.consensus);
+        n.set_mock_signature_verification(header.mock_signature_verification);
+        for (from, set) in header.validator_changes.iter() {
+            n.set_validators(*from, set.clone());
+        }
+        n.replay = Some(
+            trace
+                .entries
+                .iter()
+                .filter(|e| matches!(e.event, TraceEvent::Deliver(_)))
+                .cloned()
+                .collect(),
+        );
+
+        (n, states, dr)
+    }
+
+    // Creates the peers, one for each of the given signing providers, with
+    // `val_set` as their initial validator set.
+    fn build(
+        ctx: BaseContext,
+        signing_providers: Vec<BaseSigningProvider>,
+        val_set: BasePeerSet,
+    ) -> (Simulator, States, ProposalsSender, DecisionsReceiver) {
+        assert!(!signing_providers.is_empty(), "no peer");
 
         // Construct the simulated network
         let (ntx, nrx) = mpsc::channel();
@@ -104,36 +574,32 @@ impl Simulator {
         let (ps, pr) = cbc::bounded(5);
 
         // Channel on which to send/receive the decisions.
+        // The simulator checks every decision on its way from the applications
+        // to the environment.
         let (dtx, drx) = mpsc::channel();
+        let (checked_tx, checked_rx) = mpsc::channel();
 
-        let mut states = vec![];
+        let mut states = BTreeMap::new();
         let mut params = HashMap::new();
         let mut apps = HashMap::new();
 
-        // Construct the set of peers that comprise the network
-        let ctx = BaseContext::new();
-        let val_set = BasePeerSet::new(size, ctx.shared_public_key());
-
         // Construct the consensus states and params for each peer
-        for i in 0..size {
-            let peer_addr = BasePeerAddress::new(i);
+        for (i, signing_provider) in signing_providers.into_iter().enumerate() {
+            let peer_addr = BasePeerAddress::from(i);
             let p = Params {
                 initial_height: BaseHeight::default(),
                 initial_validator_set: val_set.clone(),
                 address: peer_addr,
-                // Note: The library provides a type and implementation
-                // for threshold params which we're re-using.
-                threshold_params: Default::default(),
-                // Todo: This can be tricky, must be documented properly
-                value_payload: ValuePayload::ProposalOnly,
+                threshold_params: ConsensusParams::default().threshold_params,
+                value_payload: ConsensusParams::default().value_payload,
             };
 
             // The params for this specific peer
             params.insert(peer_addr, p.clone());
 
-            // The state at this specific peer
-            let s = State::new(ctx.clone(), p);
-            states.push(s);
+            // The state at this specific peer, signing with its own key
+            let s = State::new(ctx.with_signing_provider(signing_provider), p);
+            states.insert(peer_addr, s);
 
             // Register the application corresponding to this peer
             apps.insert(
@@ -143,6 +609,12 @@ impl Simulator {
                     network_tx: ntx.clone(),
                     decision_tx: dtx.clone(),
                     proposal_rx: pr.clone(),
+                    timers: Timers::new(TimeoutDurations::default()),
+                    height_started_at: Duration::ZERO,
+                    mock_signature_verification: false,
+                    byzantine: ByzantineBehaviour::default(),
+                    validators: ValidatorSchedule::new(val_set.clone()),
+                    streams: PartStreams::default(),
                 },
             );
         }
@@ -153,50 +625,377 @@ impl Simulator {
                 metrics: HashMap::new(), // Initialize later, at `bootstrap` time
                 apps,
                 network_rx: nrx,
+                clock: VirtualClock::default(),
+                events: EventQueue::default(),
+                wakes: HashMap::new(),
+                network: NetworkModel::default(),
+                partition: None,
+                cut_policy: CutPolicy::default(),
+                withheld: vec![],
+                early: vec![],
+                modes: HashMap::new(),
+                step_changes: vec![],
+                steps: 0,
+                decisions_rx: drx,
+                decisions_tx: checked_tx,
+                decisions: vec![],
+                heights: BTreeMap::new(),
+                checker: SafetyChecker::default(),
+                liveness: LivenessMonitor::default(),
+                stall: None,
+                seed: 0,
+                workload: Workload::default(),
+                workload_rng: StdRng::seed_from_u64(0),
+                proposals_tx: ps.clone(),
+                proposer_selection: ctx.proposer_selection,
+                trace: None,
+                replay: None,
+                observers: vec![],
             },
             states,
             ps,
-            drx,
+            checked_rx,
         )
     }
 
+    /// Configures the parameters of consensus at every peer, see [`ConsensusParams`].
+    /// Consensus fixes its thresholds as it starts, so this replaces the
+    /// consensus `states`, and must happen before [`Simulator::initialize_system`].
+    pub fn set_consensus_params(&mut self, states: &mut States, consensus: ConsensusParams) {
+        for (peer_addr, params) in self.params.iter_mut() {
+            params.threshold_params = consensus.threshold_params;
+            params.value_payload = consensus.value_payload;
+
+            let state = states.get_mut(peer_addr).expect("state not found");
+            *state = State::new(state.ctx.clone(), params.clone());
+        }
+    }
+
+    /// The parameters of consensus which every peer shares.
+    pub fn consensus_params(&self) -> ConsensusParams {
+        let params = self.params.values().next().expect("no peer");
+
+        ConsensusParams {
+            threshold_params: params.threshold_params,
+            value_payload: params.value_payload,
+        }
+    }
+
+    /// Configures the duration of each kind of timeout, at every peer.
+    pub fn set_timeout_durations(&mut self, durations: TimeoutDurations) {
+        for app in self.apps.values_mut() {
+            app.timers.set_durations(durations);
+        }
+    }
+
+    /// Configures whether peers skip the verification of signatures,
+    /// considering every signature valid. This makes simulations faster.
+    pub fn set_mock_signature_verification(&mut self, mock: bool) {
+        for app in self.apps.values_mut() {
+            app.mock_signature_verification = mock;
+        }
+    }
+
+    /// Makes the peer at `peer` deviate from the protocol as per `behaviour`.
+    pub fn set_byzantine(&mut self, peer: BasePeerAddress, behaviour: ByzantineBehaviour) {
+        self.apps.get_mut(&peer).expect("app not found").byzantine = behaviour;
+    }
+
+    /// From height `from` onward, until the next change, the validators are
+    /// the given peers with the given voting powers.
+    /// The other peers keep following consensus, but neither vote nor propose.
+    /// Panics if every voting power is zero.
+    pub fn change_validators(
+        &mut self,
+        from: BaseHeight,
+        validators: &[(BasePeerAddress, VotingPower)],
+    ) {
+        let params = self.params.values().next().expect("no peer");
+        let set = validators::subset(&params.initial_validator_set, validators);
+        self.set_validators(from, set);
+    }
+
+    fn set_validators(&mut self, from: BaseHeight, set: BasePeerSet) {
+        for app in self.apps.values_mut() {
+            app.validators.change(from, set.clone());
+        }
+    }
+
+    /// Configures the simulated time it takes an envelope to travel between two peers,
+    /// over every link which has no faults of its own.
+    pub fn set_network_delay(&mut self, delay: Duration) {
+        self.network.default.latency = Latency::Fixed(delay);
+    }
+
+    /// Replaces the fault model of the network.
+    /// Only envelopes sent from now on are subject to the new model.
+    /// The model draws faults from its own seed: to keep a single seed for
+    /// the whole simulation, create it with [`Simulator::seed`].
+    pub fn set_network_model(&mut self, network: NetworkModel) {
+        self.network = network;
+    }
+
+    /// Follows `schedule` to put partitions in place and heal them,
+    /// replacing any partition changes which did not happen yet.
+    /// Changes scheduled in the past happen right away.
+    pub fn set_partition_schedule(&mut self, schedule: PartitionSchedule) {
+        self.cut_policy = schedule.policy;
+        self.step_changes
+            .retain(|(_, change)| !matches!(change, Change::Partition(_)));
+        self.events
+            .retain(|event| !matches!(event, Event::Change(Change::Partition(_))));
+
+        for (trigger, partition) in schedule.changes {
+            self.schedule_change(trigger, Change::Partition(partition));
+        }
+    }
+
+    /// Switches the peer at `peer` to `mode`, from now on.
+    pub fn set_peer_mode(&mut self, peer: BasePeerAddress, mode: PeerMode) {
+        self.apply_change(self.steps, Change::PeerMode(peer, mode));
+    }
+
+    /// Switches the peer at `peer` to `mode` at `trigger`.
+    /// For instance, `Trigger::Step(100)` with [`PeerMode::Crashed`] crashes
+    /// the peer from step 100 on.
+    pub fn schedule_peer_mode(&mut self, peer: BasePeerAddress, trigger: Trigger, mode: PeerMode) {
+        self.schedule_change(trigger, Change::PeerMode(peer, mode));
+    }
+
+    // Changes scheduled in the past happen right away.
+    fn schedule_change(&mut self, trigger: Trigger, change: Change) {
+        match trigger {
+            Trigger::Step(step) => {
+                // After the changes already scheduled at the same step
+                let index = self.step_changes.partition_point(|(s, _)| *s <= step);
+                self.step_changes.insert(index, (step, change));
+            }
+            Trigger::Time(time) => self
+                .events
+                .push(time.max(self.now()), Event::Change(change)),
+        }
+    }
+
+    fn apply_change(&mut self, step: u64, change: Change) {
+        self.record(step, TraceEvent::Change(change.clone()));
+
+        match change {
+            Change::Partition(partition) => self.repartition(partition),
+            Change::PeerMode(peer, mode) => {
+                info!(time = ?self.now(), %peer, ?mode, "changing the mode of a peer");
+
+                let previous = self.modes.insert(peer, mode);
+
+                // Timeouts did not elapse while the peer was down
+                if previous == Some(PeerMode::Crashed) {
+                    self.schedule_wake(peer);
+                }
+            }
+        }
+    }
+
+    fn mode(&self, peer: &BasePeerAddress) -> PeerMode {
+        self.modes.get(peer).copied().unwrap_or_default()
+    }
+
+    /// Hands `certificate` to the peer at `destination` as if it came out-of-band,
+    /// e.g., from a peer that syncs it with decisions it missed.
+    /// The peer verifies the certificate before acting upon it.
+    pub fn deliver_certificate(
+        &mut self,
+        destination: BasePeerAddress,
+        certificate: CommitCertificate<BaseContext>,
+    ) {
+        let envelope = Envelope {
+            source: destination,
+            destination,
+            payload: Message::Input(Input::CommitCertificate(certificate)),
+        };
+
+        self.events
+            .push(self.now(), Event::Deliver(Box::new(envelope)));
+    }
+
+    /// Stops the simulation once no peer decides for more than the given
+    /// number of steps or simulated time, reporting a stall.
+    /// By default, the simulation goes on as long as there are events.
+    pub fn set_stall_limits(&mut self, limits: StallLimits) {
+        self.liveness.limits = limits;
+    }
+
+    /// The stall which stopped the latest run, if any, see [`StallReport`].
+    pub fn stall(&self) -> Option<&StallReport> {
+        self.stall.as_ref()
+    }
+
+    /// The seed from which all randomness derives, see [`Simulator::with_seed`].
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Configures where the values which peers propose come from.
+    pub fn set_workload(&mut self, workload: Workload) {
+        self.workload = workload;
+    }
+
+    /// Records every envelope delivered, every fault injected, every change
+    /// to the environment, and every decision from now on, see [`Simulator::trace`].
+    pub fn record_trace(&mut self) {
+        self.trace.get_or_insert_with(Vec::new);
+    }
+
+    /// The trace recorded so far, if recording, which [`Simulator::replay`]
+    /// replays.
+    pub fn trace(&self) -> Option<Trace> {
+        let entries = self.trace.clone()?;
+        let params = self.params.values().next().expect("no peer");
+        let app = self.apps.values().next().expect("no peer");
+
+        Some(Trace {
+            header: TraceHeader {
+                seed: self.seed,
+                proposer_selection: self.proposer_selection,
+                mock_signature_verification: self
+                    .apps
+                    .values()
+                    .any(|app| app.mock_signature_verification),
+                consensus: self.consensus_params(),
+                validators: params.initial_validator_set.clone(),
+                validator_changes: app
+                    .validators
+                    .changes()
+                    .map(|(from, set)| (*from, set.clone()))
+                    .collect(),
+            },
+            entries,
+        })
+    }
+
+    fn record(&mut self, step: u64, event: TraceEvent) {
+        if let Some(trace) = &mut self.trace {
+            trace.push(TraceEntry {
+                step,
+                time: self.clock.now(),
+                event,
+            });
+        }
+    }
+
+    /// Every violation of safety so far, see [`SafetyChecker`].
+    pub fn violations(&self) -> &[ViolationReport] {
+        self.checker.violations()
+    }
+
+    /// The simulated time elapsed since the simulation started.
+    pub fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
     /// Orchestrate the execution of this system across the network of all peers.
     /// Running this will start producing [`Decision`]s.
-    pub fn run(&mut self, states: &mut [State<BaseContext>]) {
+    /// Returns once any of the `stop` conditions holds, once there is nothing
+    /// left to simulate (no envelope in flight and no pending timeout at any
+    /// peer), or once consensus stalls as per [`Simulator::set_stall_limits`].
+    /// In the latter cases, [`Simulator::stall`] describes the state of every peer.
+    pub fn run(&mut self, states: &mut States, stop: StopConditions) -> SimulationReport {
         self.initialize_system(states);
 
         // Busy loop to orchestrate among peers
-        loop {
-            // Pick the next envelope from the network and demultiplex it
-            self.step(states);
+        // Pick the next event and handle it, moving simulated time forward
+        let reason = loop {
+            if let Some(reason) = self.stop_reason(&stop) {
+                break reason;
+            }
+            if !self.step(states) {
+                break match self.stall {
+                    Some(_) => StopReason::Stalled,
+                    None => StopReason::EndOfTrace,
+                };
+            }
+        };
 
-            // Simulate network and execution delays
-            thread::sleep(STEP_DELAY);
+        warn!(time = ?self.now(), steps = self.steps, %reason, "simulation stopped");
+
+        SimulationReport {
+            reason,
+            steps: self.steps,
+            time: self.now(),
+            decisions: self.decisions.clone(),
+            heights: self.heights.clone(),
+            violations: self.violations().to_vec(),
+            stall: self.stall.clone(),
         }
     }
 
-    fn initialize_system(&mut self, states: &mut [State<BaseContext>]) {
-        let span = span!(Level::INFO, "initialize_system");
-        let _enter = span.enter();
+    // The first of the `stop` conditions which holds, if any.
+    fn stop_reason(&self, stop: &StopConditions) -> Option<StopReason> {
+        let correct = |peer: &BasePeerAddress| {
+            self.apps[peer].byzantine.is_honest() && self.mode(peer) != PeerMode::Crashed
+        };
+
+        if stop.cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+            Some(StopReason::Cancelled)
+        } else if stop.violation && !self.violations().is_empty() {
+            Some(StopReason::Violation)
+        } else if stop.height.is_some_and(|h| {
+            self.apps
+                .keys()
+                .filter(|p| correct(p))
+                .all(|p| self.heights.get(p).is_some_and(|decided| *decided >= h))
+        }) {
+            Some(StopReason::Height)
+        } else if stop.steps.is_some_and(|s| self.steps >= s) {
+            Some(StopReason::Steps)
+        } else if stop.time.is_some_and(|t| self.now() >= t) {
+            Some(StopReason::Time)
+        } else {
+            None
+        }
+    }
 
-        for peer_state in states.iter_mut() {
-            let peer_addr = peer_state.params.address;
+    /// Notifies `observer` of every decision, violation and stall from now on.
+    pub fn add_observer(&mut self, observer: Box<dyn Observer>) {
+        self.observers.push(observer);
+    }
 
-            // Potentially a future refactor: Remove `self.params` and
-            // use the ones from `states` instead.
-            let peer_params = self
-                .params
-                .get(&peer_addr)
-                .expect("could not identify peer at next position")
-                .clone();
+    /// Every decision so far, in the order they took place.
+    pub fn decisions(&self) -> &[Decision] {
+        &self.decisions
+    }
+
+    // Records a snapshot of every peer, as the simulation stalls.
+    fn report_stall(&mut self, states: &States, stall: Stall) {
+        let report = StallReport {
+            stall,
+            step: self.steps,
+            time: self.now(),
+            peers: states
+                .values()
+                .map(|s| PeerSnapshot::of(s, self.mode(&s.params.address)))
+                .collect(),
+        };
+
+        error!("{report}");
+        for observer in self.observers.iter_mut() {
+            observer.on_stall(&report);
+        }
+        self.stall = Some(report);
+    }
+
+    /// Has every peer start consensus at the first height.
+    /// Call once, before taking the first [`Simulator::step`].
+    pub fn initialize_system(&mut self, states: &mut States) {
+        let span = span!(Level::INFO, "initialize_system");
+        let _enter = span.enter();
 
+        for peer_addr in states.keys().copied() {
             // Initialize & save the metrics for later use
             let metrics = common::new_metrics();
             self.metrics.insert(peer_addr, metrics);
 
             // Tell the application at this peer to initialize itself
             let app = self.apps.get(&peer_addr).expect("app not found");
-            app.init(peer_params.initial_validator_set.clone());
+            app.init();
 
             debug!(peer = %peer_addr, "peer init done");
         }
@@ -204,61 +1003,388 @@ impl Simulator {
         info!("done");
     }
 
-    // Demultiplex among the networking envelopes incoming from `network_rx`, then
-    // calls the corresponding application logic to handle the `Input`.
-    // Blocks in case there is no envelope to handle.
-    fn step(&mut self, states: &mut [State<BaseContext>]) {
-        let network_env = self.network_rx.recv();
-        match network_env {
-            Ok(envelope) => self.step_with_envelope(states, envelope),
-            Err(err) => {
-                error!(error = ?err, "error receiving the next envelope from the network");
+    // Schedules the delivery of every envelope which the applications sent
+    // since the last call, stamping each with its arrival time.
+    // The network model decides when each envelope arrives, and how many
+    // copies of it, if any.
+    fn collect_envelopes(&mut self) {
+        let now = self.now();
+
+        while let Ok(envelope) = self.network_rx.try_recv() {
+            let mut delays = self.network.transmit(envelope.source, envelope.destination);
+
+            if envelope.source != envelope.destination {
+                match self.mode(&envelope.source) {
+                    PeerMode::Silent => delays.clear(),
+                    PeerMode::Slow(extra) => delays.iter_mut().for_each(|d| *d += extra),
+                    PeerMode::Running | PeerMode::Crashed => {}
+                }
+            }
+
+            let (source, destination) = (envelope.source, envelope.destination);
+            match delays.len() {
+                0 => {
+                    trace!(%source, %destination, "dropped envelope");
+                    self.record(
+                        self.steps,
+                        TraceEvent::Fault(Fault::Dropped {
+                            source,
+                            destination,
+                        }),
+                    );
+                }
+                1 => {}
+                _ => {
+                    trace!(%source, %destination, "duplicated envelope");
+                    self.record(
+                        self.steps,
+                        TraceEvent::Fault(Fault::Duplicated {
+                            source,
+                            destination,
+                        }),
+                    );
+                }
+            }
+
+            for delay in delays {
+                self.events
+                    .push(now + delay, Event::Deliver(Box::new(envelope.clone())));
+            }
+        }
+    }
+
+    // Tops up the values available to propose, as per the workload.
+    fn supply_proposals(&mut self) {
+        while !self.proposals_tx.is_full() {
+            let value = match &mut self.workload {
+                Workload::External => return,
+                Workload::Sequential(next) => {
+                    *next += 1;
+                    *next - 1
+                }
+                // Leave the variants of values to Byzantine peers
+                Workload::Random => self.workload_rng.gen_range(0..UNKNOWN_VALUE.0),
+            };
+
+            self.proposals_tx
+                .send(BaseValue(value))
+                .expect("could not send new value to be proposed");
+            debug!(value, "new value to be proposed");
+        }
+    }
+
+    fn crosses_partition(&self, envelope: &Envelope) -> bool {
+        self.partition
+            .as_ref()
+            .is_some_and(|p| p.separates(&envelope.source, &envelope.destination))
+    }
+
+    // Puts `partition` in place, then releases the withheld envelopes
+    // which no longer cross it.
+    fn repartition(&mut self, partition: Option<Partition>) {
+        info!(time = ?self.now(), ?partition, "changing the partition");
+        self.partition = partition;
+
+        let now = self.now();
+        let withheld = std::mem::take(&mut self.withheld);
+        for envelope in withheld {
+            if self.crosses_partition(&envelope) {
+                self.withheld.push(envelope);
+            } else {
+                self.events.push(now, Event::Deliver(Box::new(envelope)));
+            }
+        }
+    }
+
+    // Whether the envelope carries a proposal for a later height than its
+    // destination is at, with other validators than at the current height.
+    // Consensus checks the proposer of such a proposal against the current
+    // validators, and drops it, so it waits until the destination gets there.
+    fn is_early(&self, states: &States, envelope: &Envelope) -> bool {
+        let Message::Input(Input::Proposal(proposal)) = &envelope.payload else {
+            return false;
+        };
+        let current = states[&envelope.destination].driver.height();
+        let validators = &self.apps[&envelope.destination].validators;
+
+        proposal.height > current && validators.at(proposal.height) != validators.at(current)
+    }
+
+    // Releases the early proposals whose destination reached their height.
+    fn release_early(&mut self, states: &States) {
+        let now = self.now();
+        let early = std::mem::take(&mut self.early);
+        for envelope in early {
+            if self.is_early(states, &envelope) {
+                self.early.push(envelope);
+            } else {
+                self.events.push(now, Event::Deliver(Box::new(envelope)));
+            }
+        }
+    }
+
+    // Makes sure the peer wakes up when its earliest pending timeout elapses.
+    fn schedule_wake(&mut self, peer_addr: BasePeerAddress) {
+        let app = self.apps.get(&peer_addr).unwrap();
+        let Some(deadline) = app.timers.next_deadline() else {
+            return;
+        };
+
+        // Only one wake per peer is needed, as long as it is early enough.
+        // A wake for a timeout that was cancelled in the meantime is harmless.
+        if self.wakes.get(&peer_addr).is_some_and(|w| *w <= deadline) {
+            return;
+        }
+
+        // The deadline may have passed while the peer was crashed
+        let deadline = deadline.max(self.now());
+
+        self.wakes.insert(peer_addr, deadline);
+        self.events.push(deadline, Event::Wake(peer_addr));
+    }
+
+    /// Takes the earliest event, moves the simulated time forward to the moment of
+    /// that event, then handles it: either by calling the application logic
+    /// corresponding to the destination of an envelope to handle its `Input`,
+    /// or by firing the timeouts that elapsed at a peer.
+    /// Returns false if there is no event left to handle, or if consensus stalls.
+    pub fn step(&mut self, states: &mut States) -> bool {
+        if self.replay.is_some() {
+            return self.replay_step(states);
+        }
+
+        self.supply_proposals();
+        self.collect_envelopes();
+
+        while self
+            .step_changes
+            .first()
+            .is_some_and(|(step, _)| *step <= self.steps)
+        {
+            let (_, change) = self.step_changes.remove(0);
+            self.apply_change(self.steps, change);
+        }
+
+        let Some((time, event)) = self.events.pop() else {
+            // Consensus never completes all heights, so this is a stall
+            self.report_stall(states, Stall::Quiescent);
+            return false;
+        };
+        self.clock.advance_to(time);
+
+        let step = self.steps;
+        self.steps += 1;
+
+        trace!(time = ?time, pending = self.events.len(), "next event");
+
+        match event {
+            Event::Deliver(envelope) if self.mode(&envelope.destination) == PeerMode::Crashed => {
+                let (source, destination) = (envelope.source, envelope.destination);
+                trace!(%source, %destination, "envelope lost at a crashed peer");
+                self.record(
+                    step,
+                    TraceEvent::Fault(Fault::Lost {
+                        source,
+                        destination,
+                    }),
+                );
+            }
+            Event::Deliver(envelope) if self.crosses_partition(&envelope) => {
+                let (source, destination) = (envelope.source, envelope.destination);
+                trace!(%source, %destination, "envelope withheld by the partition");
+                self.record(
+                    step,
+                    TraceEvent::Fault(Fault::Withheld {
+                        source,
+                        destination,
+                    }),
+                );
+
+                if self.cut_policy == CutPolicy::Buffer {
+                    self.withheld.push(*envelope);
+                }
+            }
+            Event::Deliver(envelope) if self.is_early(states, &envelope) => {
+                trace!(destination = %envelope.destination, "proposal held back until its height");
+                self.early.push(*envelope);
+            }
+            Event::Deliver(envelope) => {
+                let peer_addr = envelope.destination;
+                self.record(step, TraceEvent::Deliver((*envelope).clone()));
+                self.step_with_envelope(states, *envelope);
+                if !self.early.is_empty() {
+                    self.release_early(states);
+                }
+
+                // Handling the input may have scheduled or cancelled timeouts
+                self.schedule_wake(peer_addr);
+            }
+            Event::Wake(peer_addr) => {
+                if self.wakes.get(&peer_addr) == Some(&time) {
+                    self.wakes.remove(&peer_addr);
+                }
+
+                // A crashed peer wakes up again once it stops being crashed
+                if self.mode(&peer_addr) != PeerMode::Crashed {
+                    let app = self.apps.get_mut(&peer_addr).unwrap();
+                    app.fire_timeouts(time);
+
+                    self.schedule_wake(peer_addr);
+                }
+            }
+            Event::Change(change) => self.apply_change(step, change),
+        }
+
+        self.check_decisions(step);
+
+        if let Some(stall) = self.liveness.check(self.steps, self.now()) {
+            self.report_stall(states, stall);
+            return false;
+        }
+
+        true
+    }
+
+    // Delivers the next envelope of the trace being replayed.
+    // Returns false if there is no envelope left to deliver.
+    fn replay_step(&mut self, states: &mut States) -> bool {
+        // The trace tells what peers receive, not what they send
+        while self.network_rx.try_recv().is_ok() {}
+
+        let Some(entry) = self.replay.as_mut().and_then(|r| r.pop_front()) else {
+            return false;
+        };
+        let TraceEvent::Deliver(envelope) = entry.event else {
+            unreachable!("only deliveries are replayed");
+        };
+
+        self.clock.advance_to(entry.time);
+        self.steps = entry.step + 1;
+
+        self.record(entry.step, TraceEvent::Deliver(envelope.clone()));
+        self.step_with_envelope(states, envelope);
+        self.check_decisions(entry.step);
+
+        true
+    }
+
+    // Checks the decisions which peers took at the given step,
+    // then forwards them to the environment.
+    fn check_decisions(&mut self, step: u64) {
+        while let Ok(decision) = self.decisions_rx.try_recv() {
+            let violations = self.checker.check(&decision, step);
+            self.liveness.decided(step, decision.time);
+            self.record(step, TraceEvent::Decide(decision.clone()));
+            debug!(
+                peer = %decision.peer,
+                value = %decision.value_id,
+                height = %decision.height,
+                round = %decision.round,
+                time = ?decision.time,
+                elapsed = ?decision.elapsed,
+                "OUT <- new decision took place",
+            );
+            self.decisions.push(decision.clone());
+            let height = self.heights.entry(decision.peer).or_default();
+            *height = decision.height.max(*height);
+
+            let reports = self.checker.violations();
+            for observer in self.observers.iter_mut() {
+                observer.on_decision(&decision);
+                for report in reports[reports.len() - violations..].iter() {
+                    observer.on_violation(report);
+                }
             }
+
+            // The environment may not listen to decisions
+            let _ = self.decisions_tx.send(decision);
         }
     }
 
-    fn step_with_envelope(&self, states: &mut [State<BaseContext>], envelope: Envelope) {
+    fn step_with_envelope(&mut self, states: &mut States, envelope: Envelope) {
         let peer_addr = envelope.destination;
+        let now = self.now();
 
-        let peer_state = states.get_mut(peer_addr.0 as usize).unwrap();
-        let params = self.params.get(&peer_addr).unwrap().clone();
-        let metrics = self.metrics.get(&peer_addr).unwrap().clone();
-        let application = self.apps.get(&peer_addr).unwrap();
+        let peer_state = states.get_mut(&peer_addr).unwrap();
+        let params = self.params.get(&peer_addr).unwrap();
+        let metrics = self.metrics.get(&peer_addr).unwrap();
+        let application = self.apps.get_mut(&peer_addr).unwrap();
 
         let context = peer_state.ctx.clone();
 
+        // Values which the application proposes are valid decisions
+        if let Message::Input(Input::Propose(v)) = &envelope.payload {
+            self.checker.proposed(v.value.id());
+        }
+
         trace!(source = %envelope.source, destination = %envelope.destination, "applying an input from an envelope");
 
-        self.apply_step_with_envelope(
+        let result = Self::apply_step_with_envelope(
             application,
             envelope.payload,
-            &params,
-            &metrics,
+            params,
+            metrics,
             peer_state,
             &context,
-        )
-        .expect("unknown error during process_peer");
+            now,
+        );
+
+        match result {
+            Ok(()) => {}
+            // The peer rejects the certificate and carries on
+            Err(Error::InvalidCertificate(certificate, e)) => {
+                warn!(peer = %peer_addr, height = %certificate.height, "rejected certificate: {e}");
+            }
+            Err(e) => panic!("unknown error during process_peer: {e}"),
+        }
     }
 
+    #[allow(clippy::result_large_err)]
     fn apply_step_with_envelope(
-        &self,
-        application: &Application,
-        input: Input<BaseContext>,
+        application: &mut Application,
+        message: Message,
         peer_params: &Params<BaseContext>,
         metrics: &Metrics,
         peer_state: &mut State<BaseContext>,
         context: &BaseContext,
+        now: Duration,
     ) -> Result<(), Error<BaseContext>> {
-        application.apply_input(input, peer_params, metrics, peer_state, context)
+        application.apply_message(message, peer_params, metrics, peer_state, context, now)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::ops::Range;
     use std::sync::mpsc::TryRecvError;
+    use std::time::Duration;
 
-    use crate::context::value::BaseValue;
-    use crate::simulator::Simulator;
+    use crate::builder::SimulatorBuilder;
+    use crate::byzantine::ByzantineBehaviour;
+    use crate::decision::Decision;
+    use crate::invariants::{Violation, ViolationReport};
+    use crate::liveness::{Stall, StallLimits};
+    use malachite_core_consensus::{Input, ValuePayload};
+    use malachite_core_types::{
+        CommitCertificate, NilOrVal, Round, SigningProvider, ThresholdParam, ThresholdParams,
+        VoteType,
+    };
+
+    use crate::context::address::BasePeerAddress;
+    use crate::context::height::BaseHeight;
+    use crate::context::proposer::ProposerSelection;
+    use crate::context::signing_provider::BaseSigningProvider;
+    use crate::context::value::{BaseValue, BaseValueId};
+    use crate::context::vote::BaseVote;
+    use crate::context::BaseContext;
+    use crate::partition::{CutPolicy, Partition, PartitionSchedule, Trigger};
+    use crate::report::{CancelHandle, StopConditions, StopReason};
+    use crate::simulator::{
+        ConsensusParams, DecisionsReceiver, Latency, LinkFaults, Message, NetworkModel, PeerMode,
+        Simulator, States, Workload,
+    };
+    use crate::timers::TimeoutDurations;
+    use crate::trace::{Trace, TraceEvent};
 
     #[test]
     fn basic_proposal_decisions() {
@@ -266,10 +1392,9 @@ mod tests {
 
         let (mut n, mut states, proposals, decisions) = Simulator::new(PEER_SET_SIZE);
         n.initialize_system(&mut states);
-        let mut proposal = 45;
         let mut peer_count = 0;
 
-        for _i in 0..10 {
+        for proposal in 45..55 {
             // Create a value to be proposed
             proposals
                 .send(BaseValue(proposal))
@@ -302,8 +1427,816 @@ mod tests {
                 }
             }
 
-            proposal += 1;
             peer_count = 0;
         }
+
+        assert!(n.violations().is_empty());
+    }
+
+    #[test]
+    fn decision_in_later_round() {
+        let (mut n, mut states, proposals, decisions) = Simulator::with_context(
+            4,
+            BaseContext::with_proposer_selection(ProposerSelection::RoundRobin),
+        );
+        n.initialize_system(&mut states);
+
+        // The first envelope starts the height at peer 0, the proposer of round 0,
+        // which finds nothing to propose
+        n.step(&mut states);
+        assert_eq!(states[&BasePeerAddress(0)].round(), Round::new(0));
+
+        // Once round 0 times out, the proposer of round 1 proposes this value
+        proposals.send(BaseValue(45)).unwrap();
+
+        let mut decided = vec![];
+        while decided.len() < 4 {
+            n.step(&mut states);
+            decided.extend(decisions.try_iter());
+        }
+
+        for d in decided {
+            assert_eq!(d.value_id.0, 45);
+            assert_eq!(d.round, Round::new(1));
+        }
+    }
+
+    #[test]
+    fn silent_proposer_leads_to_next_round() {
+        let (mut n, mut states, _proposals, decisions) = Simulator::new(4);
+        n.set_timeout_durations(TimeoutDurations::uniform(Duration::from_millis(5)));
+        n.initialize_system(&mut states);
+
+        // No value is ever proposed, so only timeouts can move consensus forward
+        while states.values().any(|s| s.round() < Round::new(1)) {
+            n.step(&mut states);
+        }
+
+        assert!(decisions.try_recv().is_err());
+    }
+
+    #[test]
+    fn certificate_without_quorum_is_rejected() {
+        let signing_providers = (0..4).map(|i| BaseSigningProvider::derive(0, i)).collect();
+        let (mut n, mut states, proposals, decisions) =
+            Simulator::with_signing_providers(BaseContext::new(), signing_providers);
+        n.initialize_system(&mut states);
+
+        // A certificate for value 99, which only peer 0 signs
+        let precommit = BaseSigningProvider::derive(0, 0).sign_vote(BaseVote {
+            vote_type: VoteType::Precommit,
+            height: BaseHeight(0),
+            value_id: NilOrVal::Val(BaseValueId(99)),
+            round: Round::new(0),
+            voter: BasePeerAddress(0),
+            extension: None,
+        });
+        let certificate = CommitCertificate::new(
+            BaseHeight(0),
+            Round::new(0),
+            BaseValueId(99),
+            vec![precommit],
+        );
+        n.deliver_certificate(BasePeerAddress(3), certificate);
+
+        // Peer 3 ignores the certificate and decides along with the others
+        proposals.send(BaseValue(45)).unwrap();
+
+        let mut decided = vec![];
+        while decided.len() < 4 {
+            n.step(&mut states);
+            decided.extend(decisions.try_iter());
+        }
+
+        assert!(decided.iter().all(|d| d.value_id.0 == 45));
+    }
+
+    #[test]
+    fn network_faults_follow_the_model() {
+        let (a, b) = (BasePeerAddress(0), BasePeerAddress(1));
+        let mut network = NetworkModel::new(LinkFaults::default(), 7);
+
+        // Envelopes to self are never faulty
+        network.default.drop = 1.0;
+        assert_eq!(network.transmit(a, a), vec![Duration::ZERO]);
+        assert!(network.transmit(a, b).is_empty());
+
+        // Links can override the default, in one direction only
+        let delay = Duration::from_millis(10);
+        network.set_link(
+            a,
+            b,
+            LinkFaults {
+                duplicate: 1.0,
+                ..LinkFaults::reliable(Latency::Fixed(delay))
+            },
+        );
+        assert_eq!(network.transmit(a, b), vec![delay, delay]);
+        assert!(network.transmit(b, a).is_empty());
+
+        // The same seed leads to the same faults
+        let lossy = LinkFaults {
+            latency: Latency::Exponential {
+                min: delay,
+                mean: delay,
+            },
+            drop: 0.3,
+            duplicate: 0.3,
+            reorder: 0.3,
+            reorder_delay: delay,
+        };
+        let sample = |seed| {
+            let mut network = NetworkModel::new(lossy, seed);
+            (0..20).map(|_| network.transmit(a, b)).collect::<Vec<_>>()
+        };
+        assert_eq!(sample(1), sample(1));
+        assert_ne!(sample(1), sample(2));
+
+        // Faults out of range apply as clamped, rather than panic
+        let clamped = LinkFaults {
+            latency: Latency::Uniform {
+                min: 2 * delay,
+                max: delay,
+            },
+            drop: -0.5,
+            duplicate: 1.5,
+            reorder: f64::NAN,
+            reorder_delay: delay,
+        };
+        assert!(clamped.check().is_err());
+        network.set_link(a, b, clamped);
+        assert_eq!(network.transmit(a, b), vec![delay, delay]);
+    }
+
+    #[test]
+    fn decisions_despite_faulty_network() {
+        let (mut n, mut states, proposals, decisions) = Simulator::new(4);
+        n.set_network_model(NetworkModel::new(
+            LinkFaults {
+                latency: Latency::Uniform {
+                    min: Duration::from_millis(50),
+                    max: Duration::from_millis(300),
+                },
+                drop: 0.05,
+                duplicate: 0.1,
+                reorder: 0.2,
+                reorder_delay: Duration::from_millis(400),
+            },
+            42,
+        ));
+        n.initialize_system(&mut states);
+
+        for value in 45..48 {
+            proposals.send(BaseValue(value)).unwrap();
+        }
+
+        let mut decided = vec![];
+        while decided.len() < 12 {
+            n.step(&mut states);
+            decided.extend(decisions.try_iter());
+        }
+
+        // Every peer decides the same values at the same heights
+        for height in 0..3 {
+            let values: Vec<_> = decided
+                .iter()
+                .filter(|d| d.height.0 == height)
+                .map(|d| d.value_id)
+                .collect();
+            assert_eq!(values.len(), 4);
+            assert!(values.iter().all(|v| *v == values[0]));
+        }
+    }
+
+    #[test]
+    fn decisions_once_partition_heals() {
+        let heal_time = Duration::from_secs(20);
+
+        for policy in [CutPolicy::Drop, CutPolicy::Buffer] {
+            let (mut n, mut states, proposals, decisions) = Simulator::new(4);
+            n.set_partition_schedule(
+                PartitionSchedule::new(policy)
+                    .split(
+                        Trigger::Step(0),
+                        Partition::new(vec![vec![0, 1], vec![2, 3]]),
+                    )
+                    .heal(Trigger::Time(heal_time)),
+            );
+            n.initialize_system(&mut states);
+
+            // Enough values for the proposer to propose in every round
+            for value in 45..50 {
+                proposals.send(BaseValue(value)).unwrap();
+            }
+
+            // Neither side has a quorum until the partition heals
+            let mut decided = vec![];
+            while decided.len() < 4 {
+                n.step(&mut states);
+                decided.extend(decisions.try_iter());
+            }
+
+            for d in decided {
+                assert!(d.time >= heal_time, "{:?}: decided at {:?}", policy, d.time);
+                assert_eq!(d.height.0, 0);
+            }
+        }
+    }
+
+    // Simulates 4 peers, the `faulty` ones Byzantine, for a minute.
+    // Peers take turns proposing, starting with peer 0.
+    // Returns the decisions, and the violations of safety.
+    fn decisions_with_byzantine_peers(
+        faulty: Range<u32>,
+        behaviour: ByzantineBehaviour,
+    ) -> (Vec<Decision>, Vec<ViolationReport>) {
+        let (mut n, mut states, proposals, decisions) = Simulator::with_context(
+            4,
+            BaseContext::with_proposer_selection(ProposerSelection::RoundRobin),
+        );
+        for peer in faulty {
+            n.set_byzantine(BasePeerAddress(peer), behaviour);
+        }
+        n.initialize_system(&mut states);
+
+        for value in 45..50 {
+            proposals.send(BaseValue(value)).unwrap();
+        }
+
+        while n.now() < Duration::from_secs(60) && n.step(&mut states) {}
+
+        (decisions.try_iter().collect(), n.violations().to_vec())
+    }
+
+    // Whether the correct peers, i.e., those not `faulty`, decide the same
+    // value at each height.
+    fn agreement(decisions: &[Decision], faulty: Range<u32>) -> bool {
+        let correct: Vec<_> = decisions
+            .iter()
+            .filter(|d| !faulty.contains(&d.peer.0))
+            .collect();
+
+        correct.iter().all(|a| {
+            correct
+                .iter()
+                .all(|b| a.height != b.height || a.value_id == b.value_id)
+        })
+    }
+
+    #[test]
+    fn safety_holds_with_one_byzantine_peer() {
+        let double_voting = ByzantineBehaviour {
+            double_prevote: true,
+            double_precommit: true,
+            ..Default::default()
+        };
+        let unknown_values = ByzantineBehaviour {
+            vote_for_unknown_values: true,
+            ..Default::default()
+        };
+
+        for behaviour in [double_voting, unknown_values] {
+            let (decided, violations) = decisions_with_byzantine_peers(0..1, behaviour);
+
+            assert!(decided.iter().any(|d| d.peer.0 != 0), "{:?}", behaviour);
+            assert!(agreement(&decided, 0..1), "{:?}: {:?}", behaviour, decided);
+            assert!(violations.is_empty(), "{:?}: {:?}", behaviour, violations);
+        }
+
+        // Peer 0 proposes first: the correct peers which receive its equivocating
+        // proposal of round 0 and lock on it may never decide, but never disagree
+        let equivocating = ByzantineBehaviour::equivocating();
+        let (decided, violations) = decisions_with_byzantine_peers(0..1, equivocating);
+        assert!(agreement(&decided, 0..1), "{:?}", decided);
+        assert!(violations.is_empty(), "{:?}", violations);
+
+        // Peer 3 proposes at height 3, once the heights before are decided,
+        // and some correct peers decide its height as well
+        let (decided, violations) = decisions_with_byzantine_peers(3..4, equivocating);
+        assert!(agreement(&decided, 3..4), "{:?}", decided);
+        assert!(violations.is_empty(), "{:?}", violations);
+        assert!(
+            decided
+                .iter()
+                .any(|d| d.peer.0 != 3 && d.height >= BaseHeight(3)),
+            "{:?}",
+            decided
+        );
+    }
+
+    #[test]
+    fn safety_breaks_with_two_byzantine_peers() {
+        let (decided, violations) =
+            decisions_with_byzantine_peers(0..2, ByzantineBehaviour::equivocating());
+
+        // Peers 2 and 3 decide different variants of the same value
+        assert!(!agreement(&decided, 0..2), "{:?}", decided);
+
+        // The checker notices as soon as it happens
+        let step = violations
+            .iter()
+            .find_map(|r| match r.violation {
+                Violation::Disagreement {
+                    height: BaseHeight(0),
+                    ..
+                } => Some(r.step),
+                _ => None,
+            })
+            .expect("no disagreement found");
+        assert!(step > 0);
+    }
+
+    #[test]
+    fn equivocating_proposers_split_prevotes_with_parts() {
+        for value_payload in [ValuePayload::PartsOnly, ValuePayload::ProposalAndParts] {
+            let (mut n, mut states, _proposals, _decisions) = SimulatorBuilder::new(4)
+                .seed(1)
+                .context(BaseContext::with_proposer_selection(
+                    ProposerSelection::RoundRobin,
+                ))
+                .consensus_params(ConsensusParams {
+                    value_payload,
+                    ..Default::default()
+                })
+                .byzantine(
+                    BasePeerAddress(0),
+                    ByzantineBehaviour {
+                        equivocate_proposals: true,
+                        ..Default::default()
+                    },
+                )
+                .workload(Workload::Sequential(45))
+                .record_trace()
+                .build();
+
+            let stop = StopConditions {
+                time: Some(Duration::from_secs(10)),
+                ..Default::default()
+            };
+            n.run(&mut states, stop);
+
+            // Peer 0 proposes first, and the correct peers prevote for the
+            // variant of the value which was streamed to them
+            let trace = n.trace().unwrap();
+            let prevote = |voter| {
+                trace.entries.iter().find_map(|e| match &e.event {
+                    TraceEvent::Deliver(envelope) => match &envelope.payload {
+                        Message::Input(Input::Vote(sv))
+                            if sv.voter == BasePeerAddress(voter)
+                                && sv.vote_type == VoteType::Prevote
+                                && sv.height == BaseHeight(0)
+                                && sv.round == Round::new(0) =>
+                        {
+                            Some(sv.value_id)
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                })
+            };
+            let (even, odd) = (prevote(2), prevote(3));
+            assert!(
+                matches!(even, Some(NilOrVal::Val(_))),
+                "{value_payload:?}: {even:?}"
+            );
+            assert!(
+                matches!(odd, Some(NilOrVal::Val(_))),
+                "{value_payload:?}: {odd:?}"
+            );
+            assert_ne!(even, odd, "{value_payload:?}");
+            assert_eq!(prevote(1), odd, "{value_payload:?}");
+        }
+    }
+
+    #[test]
+    fn liveness_with_the_first_peer_down() {
+        let down = Duration::from_secs(20);
+
+        for (selection, mode, recovers, live) in [
+            (ProposerSelection::Fixed, PeerMode::Crashed, false, false),
+            (ProposerSelection::Fixed, PeerMode::Silent, false, false),
+            (ProposerSelection::Fixed, PeerMode::Crashed, true, true),
+            (
+                ProposerSelection::Fixed,
+                PeerMode::Slow(Duration::from_secs(1)),
+                false,
+                true,
+            ),
+            (
+                ProposerSelection::RoundRobin,
+                PeerMode::Crashed,
+                false,
+                true,
+            ),
+            (ProposerSelection::RoundRobin, PeerMode::Silent, false, true),
+        ] {
+            let (mut n, mut states, proposals, decisions) =
+                Simulator::with_context(4, BaseContext::with_proposer_selection(selection));
+            // Once every peer started height 0, before peer 0 handles its own proposal
+            n.schedule_peer_mode(BasePeerAddress(0), Trigger::Step(4), mode);
+            if recovers {
+                n.schedule_peer_mode(BasePeerAddress(0), Trigger::Time(down), PeerMode::Running);
+            }
+            n.initialize_system(&mut states);
+
+            for value in 45..50 {
+                proposals.send(BaseValue(value)).unwrap();
+            }
+
+            while n.now() < Duration::from_secs(60) && n.step(&mut states) {}
+            let decided: Vec<_> = decisions.try_iter().collect();
+
+            let case = format!("{:?} with {:?} (recovers: {})", selection, mode, recovers);
+            assert_eq!(!decided.is_empty(), live, "{case}");
+            assert!(agreement(&decided, 0..0), "{case}");
+            if recovers {
+                assert!(decided.iter().all(|d| d.time >= down), "{case}");
+            }
+        }
+    }
+
+    #[test]
+    fn stall_reports_the_state_of_every_peer() {
+        let (mut n, mut states, proposals, _decisions) = Simulator::with_context(
+            4,
+            BaseContext::with_proposer_selection(ProposerSelection::Fixed),
+        );
+        n.schedule_peer_mode(BasePeerAddress(0), Trigger::Step(4), PeerMode::Crashed);
+        n.set_stall_limits(StallLimits {
+            steps: None,
+            time: Some(Duration::from_secs(10)),
+        });
+        n.initialize_system(&mut states);
+
+        for value in 45..50 {
+            proposals.send(BaseValue(value)).unwrap();
+        }
+
+        // The stall stops the simulation, which would go on forever otherwise
+        while n.step(&mut states) {}
+
+        let report = n.stall().expect("no stall reported");
+        assert!(matches!(report.stall, Stall::NoDecision { .. }));
+        assert!(report.time > Duration::from_secs(10));
+        assert_eq!(report.peers.len(), 4);
+
+        for peer in report.peers.iter() {
+            let mode = match peer.peer.0 {
+                0 => PeerMode::Crashed,
+                _ => PeerMode::Running,
+            };
+            assert_eq!(peer.mode, mode);
+            assert_eq!(peer.height, BaseHeight(0));
+        }
+        // The correct peers moved on to later rounds without peer 0
+        assert!(report.peers[1..].iter().all(|p| p.round > Round::new(0)));
+    }
+
+    // A simulator with a seed, proposers taking turns, a faulty network,
+    // and random values to propose.
+    fn seeded_simulation(seed: u64) -> (Simulator, States, DecisionsReceiver) {
+        let (mut n, states, _proposals, decisions) = Simulator::with_seed(
+            4,
+            BaseContext::with_proposer_selection(ProposerSelection::RoundRobin),
+            seed,
+        );
+        n.set_network_model(NetworkModel::new(
+            LinkFaults {
+                latency: Latency::Uniform {
+                    min: Duration::from_millis(50),
+                    max: Duration::from_millis(300),
+                },
+                drop: 0.05,
+                duplicate: 0.1,
+                reorder: 0.2,
+                reorder_delay: Duration::from_millis(400),
+            },
+            n.seed(),
+        ));
+        n.set_workload(Workload::Random);
+
+        (n, states, decisions)
+    }
+
+    #[test]
+    fn same_seed_same_trace() {
+        let trace = |seed| {
+            let (mut n, mut states, _decisions) = seeded_simulation(seed);
+            n.record_trace();
+            n.initialize_system(&mut states);
+
+            for _ in 0..500 {
+                n.step(&mut states);
+            }
+
+            let mut bytes = vec![];
+            n.trace().unwrap().write_to(&mut bytes).unwrap();
+            bytes
+        };
+
+        let first = trace(7);
+        assert!(String::from_utf8_lossy(&first).contains(" decide "));
+        assert_eq!(first, trace(7));
+        assert_ne!(first, trace(8));
+    }
+
+    #[test]
+    fn replay_takes_the_same_decisions() {
+        let (mut n, mut states, decisions) = seeded_simulation(3);
+        n.schedule_peer_mode(BasePeerAddress(3), Trigger::Step(50), PeerMode::Crashed);
+        n.schedule_peer_mode(BasePeerAddress(3), Trigger::Step(150), PeerMode::Running);
+        n.record_trace();
+        n.initialize_system(&mut states);
+
+        for _ in 0..400 {
+            n.step(&mut states);
+        }
+        let original: Vec<_> = decisions.try_iter().collect();
+        assert!(!original.is_empty());
+
+        // The trace survives a round trip through its file format
+        let trace = n.trace().unwrap();
+        let mut bytes = vec![];
+        trace.write_to(&mut bytes).unwrap();
+        let loaded = Trace::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(loaded, trace);
+
+        let (mut r, mut states, decisions) = Simulator::replay(&loaded);
+        r.initialize_system(&mut states);
+        while r.step(&mut states) {}
+
+        let replayed: Vec<_> = decisions.try_iter().collect();
+        assert_eq!(replayed, original);
+        assert_eq!(loaded.decisions().cloned().collect::<Vec<_>>(), original);
+    }
+
+    #[test]
+    fn run_stops_at_the_first_condition_which_holds() {
+        let simulation = || {
+            let (mut n, states, _proposals, decisions) =
+                Simulator::with_seed(4, BaseContext::new(), 1);
+            n.set_workload(Workload::Sequential(45));
+            (n, states, decisions)
+        };
+
+        let (mut n, mut states, decisions) = simulation();
+        let stop = StopConditions {
+            height: Some(BaseHeight(2)),
+            steps: Some(10_000),
+            ..Default::default()
+        };
+        let report = n.run(&mut states, stop);
+        assert_eq!(report.reason, StopReason::Height);
+        assert!(report.heights.values().all(|h| *h >= BaseHeight(2)));
+        assert_eq!(report.decisions, decisions.try_iter().collect::<Vec<_>>());
+        assert!(report.is_success());
+
+        let (mut n, mut states, _) = simulation();
+        let stop = StopConditions {
+            steps: Some(50),
+            ..Default::default()
+        };
+        let report = n.run(&mut states, stop);
+        assert_eq!((report.reason, report.steps), (StopReason::Steps, 50));
+
+        // Cancelled from the start, e.g., by another thread
+        let (mut n, mut states, _) = simulation();
+        let cancel = CancelHandle::default();
+        cancel.cancel();
+        let stop = StopConditions {
+            cancel: Some(cancel),
+            ..Default::default()
+        };
+        let report = n.run(&mut states, stop);
+        assert_eq!((report.reason, report.steps), (StopReason::Cancelled, 0));
+    }
+
+    #[test]
+    fn quorum_follows_voting_power() {
+        // Peer 0 holds 5 of the 8 units of voting power, so it reaches a quorum
+        // (more than 2/3, i.e., at least 6) with any light peer, and the light
+        // peers never reach one without it
+        let simulation = |crashed: &[u32]| {
+            let (mut n, mut states, _proposals, _decisions) = Simulator::with_voting_powers(
+                BaseContext::with_proposer_selection(ProposerSelection::RoundRobin),
+                2,
+                vec![5, 1, 1, 1],
+            );
+            n.set_workload(Workload::Sequential(45));
+            n.set_stall_limits(StallLimits {
+                steps: None,
+                time: Some(Duration::from_secs(30)),
+            });
+            for peer in crashed {
+                n.set_peer_mode(BasePeerAddress(*peer), PeerMode::Crashed);
+            }
+
+            let stop = StopConditions {
+                height: Some(BaseHeight(2)),
+                ..Default::default()
+            };
+            n.run(&mut states, stop)
+        };
+
+        let report = simulation(&[2, 3]);
+        assert_eq!(report.reason, StopReason::Height);
+        assert_eq!(report.heights[&BasePeerAddress(0)], BaseHeight(2));
+
+        let report = simulation(&[0]);
+        assert_eq!(report.reason, StopReason::Stalled);
+        assert!(report.decisions.is_empty());
+    }
+
+    #[test]
+    fn validators_change_across_heights() {
+        // Peer 4 only follows consensus until height 2, from which it
+        // takes the place of peer 0. Latencies vary, so that some proposals
+        // arrive before their destination reaches their height
+        let (mut n, mut states, _proposals, _decisions) = SimulatorBuilder::new(5)
+            .seed(2)
+            .faults(LinkFaults {
+                latency: Latency::Uniform {
+                    min: Duration::from_millis(50),
+                    max: Duration::from_millis(300),
+                },
+                ..Default::default()
+            })
+            .context(BaseContext::with_proposer_selection(
+                ProposerSelection::RoundRobin,
+            ))
+            .workload(Workload::Sequential(45))
+            .validators_from(
+                BaseHeight(0),
+                (0..4).map(|i| (BasePeerAddress(i), 1)).collect(),
+            )
+            .validators_from(
+                BaseHeight(2),
+                (1..5).map(|i| (BasePeerAddress(i), 1)).collect(),
+            )
+            .record_trace()
+            .build();
+
+        let stop = StopConditions {
+            height: Some(BaseHeight(4)),
+            time: Some(Duration::from_secs(300)),
+            ..Default::default()
+        };
+        let report = n.run(&mut states, stop);
+        assert_eq!(report.reason, StopReason::Height);
+        assert!(report.heights.values().all(|h| *h >= BaseHeight(4)));
+        assert!(report.is_success());
+
+        // Only the validators of a height vote at that height
+        let trace = n.trace().unwrap();
+        for entry in trace.entries.iter() {
+            let TraceEvent::Deliver(envelope) = &entry.event else {
+                continue;
+            };
+            if let Message::Input(Input::Vote(vote)) = &envelope.payload {
+                let leaving = vote.voter == BasePeerAddress(0) && vote.height >= BaseHeight(2);
+                let joining = vote.voter == BasePeerAddress(4) && vote.height < BaseHeight(2);
+                assert!(!leaving && !joining, "unexpected vote {}", vote.message);
+            }
+        }
+
+        // Replays know about the changes
+        let mut bytes = vec![];
+        trace.write_to(&mut bytes).unwrap();
+        let loaded = Trace::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(loaded.header.validator_changes.len(), 2);
+
+        let (mut r, mut states, decisions) = Simulator::replay(&loaded);
+        r.initialize_system(&mut states);
+        while r.step(&mut states) {}
+        let replayed: Vec<_> = decisions.try_iter().collect();
+        assert_eq!(replayed, loaded.decisions().cloned().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn networks_of_any_size_decide() {
+        for peers in [1, 2, 3, 40] {
+            let (mut n, mut states, _proposals, _decisions) = SimulatorBuilder::new(peers)
+                .seed(peers as u64)
+                .mock_signatures(true)
+                .workload(Workload::Sequential(45))
+                .build();
+            assert!(states.keys().copied().eq((0..peers).map(BasePeerAddress)));
+
+            let stop = StopConditions {
+                height: Some(BaseHeight(1)),
+                time: Some(Duration::from_secs(60)),
+                ..Default::default()
+            };
+            let report = n.run(&mut states, stop);
+            assert_eq!(report.reason, StopReason::Height, "{peers} peers");
+            assert_eq!(report.heights.len(), peers as usize);
+        }
+    }
+
+    #[test]
+    fn every_value_payload_decides() {
+        for value_payload in [
+            ValuePayload::ProposalOnly,
+            ValuePayload::PartsOnly,
+            ValuePayload::ProposalAndParts,
+        ] {
+            let (mut n, mut states, _proposals, _decisions) = SimulatorBuilder::new(4)
+                .seed(3)
+                .consensus_params(ConsensusParams {
+                    value_payload,
+                    ..Default::default()
+                })
+                .faults(LinkFaults {
+                    latency: Latency::Uniform {
+                        min: Duration::from_millis(50),
+                        max: Duration::from_millis(300),
+                    },
+                    reorder: 0.2,
+                    duplicate: 0.1,
+                    ..Default::default()
+                })
+                .workload(Workload::Sequential(45))
+                .record_trace()
+                .build();
+
+            let stop = StopConditions {
+                height: Some(BaseHeight(3)),
+                time: Some(Duration::from_secs(300)),
+                ..Default::default()
+            };
+            let report = n.run(&mut states, stop);
+            assert_eq!(report.reason, StopReason::Height, "{value_payload:?}");
+            assert!(report.is_success());
+
+            // Proposals only travel between peers if consensus publishes them
+            let trace = n.trace().unwrap();
+            let proposals = trace.entries.iter().filter(|e| {
+                matches!(&e.event, TraceEvent::Deliver(envelope)
+                    if envelope.source != envelope.destination
+                        && matches!(envelope.payload, Message::Input(Input::Proposal(_))))
+            });
+            assert_eq!(
+                proposals.count() > 0,
+                value_payload.include_proposal(),
+                "{value_payload:?}"
+            );
+
+            // Values travel in parts if consensus expects parts
+            let parts = trace.entries.iter().filter(|e| {
+                matches!(&e.event, TraceEvent::Deliver(envelope)
+                    if matches!(envelope.payload, Message::ProposalPart(_)))
+            });
+            assert_eq!(
+                parts.count() > 0,
+                value_payload.include_parts(),
+                "{value_payload:?}"
+            );
+
+            // Replays run with the same parameters
+            let (mut r, mut states, decisions) = Simulator::replay(&trace);
+            assert_eq!(r.consensus_params(), n.consensus_params());
+            r.initialize_system(&mut states);
+            while r.step(&mut states) {}
+            let replayed: Vec<_> = decisions.try_iter().collect();
+            assert_eq!(replayed, trace.decisions().cloned().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn quorum_follows_the_threshold_params() {
+        // With one of four equal peers crashed, the others hold 3/4 of the
+        // voting power: more than the default 2/3, but not more than 3/4
+        let simulation = |quorum| {
+            let threshold_params = ThresholdParams {
+                quorum,
+                ..Default::default()
+            };
+            let (mut n, mut states, _proposals, _decisions) = SimulatorBuilder::new(4)
+                .seed(1)
+                .consensus_params(ConsensusParams {
+                    threshold_params,
+                    ..Default::default()
+                })
+                .workload(Workload::Sequential(45))
+                .stall_limits(StallLimits {
+                    steps: None,
+                    time: Some(Duration::from_secs(30)),
+                })
+                .build();
+            n.set_peer_mode(BasePeerAddress(3), PeerMode::Crashed);
+
+            let stop = StopConditions {
+                height: Some(BaseHeight(1)),
+                ..Default::default()
+            };
+            n.run(&mut states, stop)
+        };
+
+        let report = simulation(ThresholdParam::TWO_F_PLUS_ONE);
+        assert_eq!(report.reason, StopReason::Height);
+
+        let report = simulation(ThresholdParam::new(3, 4));
+        assert_eq!(report.reason, StopReason::Stalled);
+        assert!(report.decisions.is_empty());
     }
 }
@@ -0,0 +1,171 @@
+/// Streaming of proposed values as parts, see [`BaseProposalPart`].
+///
+/// The proposer of a value splits it into an init part, data parts of at most
+/// [`PART_SIZE`] bytes each and a fin part, numbered in sequence. Each peer
+/// buffers the parts of every stream until it holds all of them, whichever
+/// order they arrive in and however many times, then reassembles the value.
+///
+use std::collections::BTreeMap;
+
+use malachite_core_consensus::ProposedValue;
+use malachite_core_types::{ProposalPart, Round, Validity};
+
+use crate::context::address::BasePeerAddress;
+use crate::context::height::BaseHeight;
+use crate::context::proposals::{BaseProposalPart, PartContent};
+use crate::context::value::BaseValue;
+use crate::context::BaseContext;
+
+/// The most bytes of a value which one data part carries.
+pub const PART_SIZE: usize = 3;
+
+/// Identifies a stream: the height and round of the proposal, and its proposer.
+pub type StreamId = (BaseHeight, Round, BasePeerAddress);
+
+/// Splits `value` into the sequence of parts which stream it.
+pub fn split(value: &ProposedValue<BaseContext>) -> Vec<BaseProposalPart> {
+    let bytes = value.value.0.to_be_bytes();
+
+    let contents = std::iter::once(PartContent::Init {
+        valid_round: value.valid_round,
+    })
+    .chain(
+        bytes
+            .chunks(PART_SIZE)
+            .map(|c| PartContent::Data(c.to_vec())),
+    )
+    .chain(std::iter::once(PartContent::Fin));
+
+    contents
+        .enumerate()
+        .map(|(sequence, content)| BaseProposalPart {
+            height: value.height,
+            round: value.round,
+            proposer: value.proposer,
+            sequence: sequence as u64,
+            content,
+        })
+        .collect()
+}
+
+// Reassembles a value from all the parts of its stream, in sequence.
+// Returns nothing if the parts do not form a value.
+fn assemble(
+    mut parts: impl Iterator<Item = BaseProposalPart>,
+) -> Option<ProposedValue<BaseContext>> {
+    let first = parts.next()?;
+    let PartContent::Init { valid_round } = first.content else {
+        return None;
+    };
+
+    let mut bytes = vec![];
+    for part in parts {
+        match part.content {
+            PartContent::Data(data) => bytes.extend(data),
+            PartContent::Fin => break,
+            PartContent::Init { .. } => return None,
+        }
+    }
+
+    Some(ProposedValue {
+        height: first.height,
+        round: first.round,
+        valid_round,
+        proposer: first.proposer,
+        value: BaseValue(u64::from_be_bytes(bytes.try_into().ok()?)),
+        validity: Validity::Valid,
+        extension: None,
+    })
+}
+
+/// The parts which a peer received, by stream, until their stream is complete.
+#[derive(Debug, Default)]
+pub struct PartStreams {
+    streams: BTreeMap<StreamId, BTreeMap<u64, BaseProposalPart>>,
+}
+
+impl PartStreams {
+    /// Buffers `part`, and returns the value of its stream once the stream is
+    /// complete, i.e., once the buffer holds the fin and every part before it.
+    /// A complete stream which does not form a value is dropped.
+    pub fn insert(&mut self, part: BaseProposalPart) -> Option<ProposedValue<BaseContext>> {
+        let id = (part.height, part.round, part.proposer);
+        let parts = self.streams.entry(id).or_default();
+        parts.insert(part.sequence, part);
+
+        // Sequences start at 0, so all parts arrived if the last one is the
+        // fin and there are as many parts as its sequence says
+        let (sequence, last) = parts.last_key_value()?;
+        if !last.is_last() || *sequence + 1 != parts.len() as u64 {
+            return None;
+        }
+
+        let parts = self.streams.remove(&id)?;
+        assemble(parts.into_values())
+    }
+
+    /// Forgets the streams of `height` and of the heights before it.
+    pub fn prune(&mut self, height: BaseHeight) {
+        self.streams.retain(|(h, _, _), _| *h > height);
+    }
+
+    /// How many streams are incomplete.
+    pub fn len(&self) -> usize {
+        self.streams.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use malachite_core_consensus::ProposedValue;
+    use malachite_core_types::{Round, Validity};
+
+    use crate::context::address::BasePeerAddress;
+    use crate::context::height::BaseHeight;
+    use crate::context::value::BaseValue;
+    use crate::context::BaseContext;
+    use crate::streaming::{split, PartStreams};
+
+    fn value(height: u64, value: u64) -> ProposedValue<BaseContext> {
+        ProposedValue {
+            height: BaseHeight(height),
+            round: Round::new(1),
+            valid_round: Round::new(0),
+            proposer: BasePeerAddress(2),
+            value: BaseValue(value),
+            validity: Validity::Valid,
+            extension: None,
+        }
+    }
+
+    #[test]
+    fn values_reassemble_from_reordered_and_duplicated_parts() {
+        let parts = split(&value(3, 0x0102_0304_0506_0708));
+        // An init, 8 bytes in 3 chunks, then a fin
+        assert_eq!(parts.len(), 5);
+
+        let mut streams = PartStreams::default();
+        let mut emitted = vec![];
+        for i in [4, 2, 2, 0, 4, 3, 1] {
+            emitted.extend(streams.insert(parts[i].clone()));
+        }
+        assert_eq!(emitted, vec![value(3, 0x0102_0304_0506_0708)]);
+        assert!(streams.is_empty());
+
+        // Streams of different values do not mix, and incomplete ones are pruned
+        let mut other = split(&value(4, 45));
+        other.pop();
+        for part in other {
+            assert_eq!(streams.insert(part), None);
+        }
+        assert_eq!(streams.len(), 1);
+        streams.prune(BaseHeight(3));
+        assert_eq!(streams.len(), 1);
+        streams.prune(BaseHeight(4));
+        assert!(streams.is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+use std::time::Duration;
+use tracing::trace;
+
+use malachite_core_types::{Timeout, TimeoutKind};
+
+/// The duration of each kind of [`Timeout`].
+///
+/// The propose, prevote and precommit timeouts grow by their respective
+/// `*_delta` every time a timeout of that kind elapses, so that consensus
+/// eventually makes progress in later rounds.
+/// The growth is undone when consensus asks to reset the timeouts,
+/// which happens at the start of every height.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TimeoutDurations {
+    pub propose: Duration,
+    pub propose_delta: Duration,
+    pub prevote: Duration,
+    pub prevote_delta: Duration,
+    pub precommit: Duration,
+    pub precommit_delta: Duration,
+    pub commit: Duration,
+    /// Applies to both [`TimeoutKind::PrevoteTimeLimit`] and
+    /// [`TimeoutKind::PrecommitTimeLimit`].
+    pub step_limit: Duration,
+}
+
+impl Default for TimeoutDurations {
+    fn default() -> Self {
+        Self {
+            propose: Duration::from_secs(3),
+            propose_delta: Duration::from_millis(500),
+            prevote: Duration::from_secs(1),
+            prevote_delta: Duration::from_millis(500),
+            precommit: Duration::from_secs(1),
+            precommit_delta: Duration::from_millis(500),
+            // The commit timeout elapses as soon as the simulator takes its next step.
+            commit: Duration::ZERO,
+            step_limit: Duration::from_secs(30),
+        }
+    }
+}
+
+impl TimeoutDurations {
+    /// The same duration for every kind of timeout, except for the commit
+    /// timeout which stays instantaneous. No timeout grows when elapsing.
+    pub fn uniform(duration: Duration) -> Self {
+        Self {
+            propose: duration,
+            propose_delta: Duration::ZERO,
+            prevote: duration,
+            prevote_delta: Duration::ZERO,
+            precommit: duration,
+            precommit_delta: Duration::ZERO,
+            commit: Duration::ZERO,
+            step_limit: duration,
+        }
+    }
+
+    pub fn duration_for(&self, kind: TimeoutKind) -> Duration {
+        match kind {
+            TimeoutKind::Propose => self.propose,
+            TimeoutKind::Prevote => self.prevote,
+            TimeoutKind::Precommit => self.precommit,
+            TimeoutKind::Commit => self.commit,
+            TimeoutKind::PrevoteTimeLimit | TimeoutKind::PrecommitTimeLimit => self.step_limit,
+        }
+    }
+
+    fn increase(&mut self, kind: TimeoutKind) {
+        match kind {
+            TimeoutKind::Propose => self.propose += self.propose_delta,
+            TimeoutKind::Prevote => self.prevote += self.prevote_delta,
+            TimeoutKind::Precommit => self.precommit += self.precommit_delta,
+            TimeoutKind::Commit
+            | TimeoutKind::PrevoteTimeLimit
+            | TimeoutKind::PrecommitTimeLimit => {}
+        }
+    }
+}
+
+/// The timeouts which consensus scheduled at a single peer.
+///
+/// Time is measured as the [`Duration`] elapsed since the simulator started,
+/// so each scheduled timeout is kept along with the deadline at which it elapses.
+#[derive(Clone, Debug)]
+pub struct Timers {
+    // The durations as configured, used when resetting.
+    initial: TimeoutDurations,
+
+    // The durations currently in use, possibly increased by elapsed timeouts.
+    current: TimeoutDurations,
+
+    // The pending timeouts in the order they were scheduled, with their deadline.
+    scheduled: Vec<(Timeout, Duration)>,
+}
+
+impl Timers {
+    pub fn new(durations: TimeoutDurations) -> Self {
+        Self {
+            initial: durations,
+            current: durations,
+            scheduled: vec![],
+        }
+    }
+
+    /// Replaces the configured durations. Pending timeouts keep their deadline.
+    pub fn set_durations(&mut self, durations: TimeoutDurations) {
+        self.initial = durations;
+        self.current = durations;
+    }
+
+    /// Schedules timeout `t` to elapse after its duration, counting from `now`.
+    /// Re-scheduling a pending timeout overrides its deadline.
+    pub fn schedule(&mut self, t: Timeout, now: Duration) {
+        let deadline = now + self.current.duration_for(t.kind);
+        trace!(timeout = %t, ?deadline, "scheduled");
+
+        self.cancel(&t);
+        self.scheduled.push((t, deadline));
+    }
+
+    pub fn cancel(&mut self, t: &Timeout) {
+        self.scheduled.retain(|(s, _)| s != t);
+    }
+
+    pub fn cancel_all(&mut self) {
+        self.scheduled.clear();
+    }
+
+    /// Restores the durations to their initial, configured values.
+    pub fn reset(&mut self) {
+        self.current = self.initial;
+    }
+
+    /// The earliest deadline among all the pending timeouts.
+    pub fn next_deadline(&self) -> Option<Duration> {
+        self.scheduled.iter().map(|(_, d)| *d).min()
+    }
+
+    /// Removes and returns the timeouts whose deadline is at or before `now`,
+    /// ordered by their deadline.
+    /// Every timeout that elapses increases the duration of its kind.
+    pub fn expired(&mut self, now: Duration) -> Vec<Timeout> {
+        let (mut expired, pending): (Vec<_>, Vec<_>) =
+            self.scheduled.drain(..).partition(|(_, d)| *d <= now);
+        self.scheduled = pending;
+
+        // Stable sort, so timeouts with the same deadline keep their scheduling order
+        expired.sort_by_key(|(_, d)| *d);
+
+        expired
+            .into_iter()
+            .map(|(t, _)| {
+                self.current.increase(t.kind);
+                t
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use malachite_core_types::{Round, Timeout};
+
+    use crate::timers::{TimeoutDurations, Timers};
+
+    #[test]
+    fn timeouts_expire_in_deadline_order() {
+        let mut timers = Timers::new(TimeoutDurations::default());
+        let start = Duration::ZERO;
+
+        timers.schedule(Timeout::propose(Round::new(0)), start);
+        timers.schedule(Timeout::prevote(Round::new(0)), start);
+        timers.schedule(Timeout::commit(Round::new(0)), start);
+        assert_eq!(timers.next_deadline(), Some(start));
+
+        // Only the commit timeout is instantaneous
+        assert_eq!(timers.expired(start), vec![Timeout::commit(Round::new(0))]);
+
+        // The prevote timeout (1s) elapses before the propose timeout (3s)
+        assert_eq!(
+            timers.expired(Duration::from_secs(5)),
+            vec![
+                Timeout::prevote(Round::new(0)),
+                Timeout::propose(Round::new(0))
+            ]
+        );
+        assert_eq!(timers.next_deadline(), None);
+
+        // Cancelled timeouts never expire
+        timers.schedule(Timeout::propose(Round::new(1)), start);
+        timers.schedule(Timeout::prevote(Round::new(1)), start);
+        timers.cancel(&Timeout::propose(Round::new(1)));
+        assert_eq!(
+            timers.expired(Duration::from_secs(60)),
+            vec![Timeout::prevote(Round::new(1))]
+        );
+
+        timers.schedule(Timeout::precommit(Round::new(1)), start);
+        timers.cancel_all();
+        assert!(timers.expired(Duration::from_secs(60)).is_empty());
+    }
+
+    #[test]
+    fn elapsed_timeouts_grow_until_reset() {
+        let durations = TimeoutDurations::default();
+        let mut timers = Timers::new(durations);
+
+        timers.schedule(Timeout::propose(Round::new(0)), Duration::ZERO);
+        timers.expired(durations.propose);
+
+        // The next propose timeout lasts one delta longer
+        timers.schedule(Timeout::propose(Round::new(1)), Duration::ZERO);
+        assert_eq!(
+            timers.next_deadline(),
+            Some(durations.propose + durations.propose_delta)
+        );
+
+        timers.reset();
+        timers.schedule(Timeout::propose(Round::new(1)), Duration::ZERO);
+        assert_eq!(timers.next_deadline(), Some(durations.propose));
+    }
+}
@@ -0,0 +1,977 @@
+/// Traces of simulations: every envelope delivered, every fault injected and
+/// every decision, in the order they happen, as a file which the simulator
+/// can replay.
+///
+/// A trace file is text. It starts with a header describing the peers,
+/// one field per line, followed by one line per entry:
+///
+/// ```text
+/// malachite-simulator-trace 1
+/// seed 7
+/// proposer round-robin
+/// mock-signatures false
+/// thresholds 2/3 1/3
+/// value-payload proposal-only
+/// validators 0:<public key>:<voting power>,1:<public key>:<voting power>,...
+/// validators-from 5 1:<public key>:<voting power>,2:<public key>:<voting power>,...
+/// 12 400000000 deliver 1 2 vote <vote>:<signature>
+/// 12 400000000 drop 2 3
+/// 13 400000000 deliver 1 3 part <part>:<signature>
+/// 13 600000000 decide 2 0 0 45 600000000
+/// ```
+///
+/// Each entry starts with the step of the simulator and the simulated time, in
+/// nanoseconds. Signed messages are the hexadecimal encoding of their canonical
+/// bytes (see [`crate::context::encoding`]) and of their signature.
+///
+use std::fmt;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str::{FromStr, SplitWhitespace};
+use std::time::Duration;
+
+use bytes::Bytes;
+use malachite_core_consensus::{Input, ProposedValue, ValuePayload, ValueToPropose};
+use malachite_core_types::{
+    AggregatedSignature, CommitCertificate, CommitSignature, Extension, Round, SignedExtension,
+    SignedMessage, SignedProposal, SignedProposalPart, SignedVote, ThresholdParam, ThresholdParams,
+    Timeout, TimeoutKind, Validity, Value as _, ValueOrigin, VoteSet,
+};
+
+use crate::context::address::BasePeerAddress;
+use crate::context::height::BaseHeight;
+use crate::context::peer::BasePeer;
+use crate::context::peer_set::BasePeerSet;
+use crate::context::proposals::{BaseProposal, BaseProposalPart};
+use crate::context::proposer::ProposerSelection;
+use crate::context::signing_scheme::{PublicKey, Signature};
+use crate::context::value::{BaseValue, BaseValueId};
+use crate::context::vote::BaseVote;
+use crate::context::BaseContext;
+use crate::decision::Decision;
+use crate::invariants::{SafetyChecker, ViolationReport};
+use crate::partition::Partition;
+use crate::simulator::{Change, ConsensusParams, Envelope, Message, PeerMode};
+
+/// The version of the trace format. Loading rejects any other version.
+pub const TRACE_VERSION: u32 = 1;
+
+const MAGIC: &str = "malachite-simulator-trace";
+
+/// What a replay needs to know about the simulation, besides its entries.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceHeader {
+    /// The seed of the simulator, see [`crate::simulator::Simulator::with_seed`].
+    pub seed: u64,
+    pub proposer_selection: ProposerSelection,
+    pub mock_signature_verification: bool,
+    pub consensus: ConsensusParams,
+    /// The peers and their public keys, which signatures verify against.
+    pub validators: BasePeerSet,
+    /// The validators from each height onward, see
+    /// [`crate::simulator::Simulator::change_validators`].
+    pub validator_changes: Vec<(BaseHeight, BasePeerSet)>,
+}
+
+/// An envelope which the simulator did not deliver as the network model or the
+/// peer modes would have it, or delivered more than once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// The network lost the envelope, or its source is silent.
+    Dropped {
+        source: BasePeerAddress,
+        destination: BasePeerAddress,
+    },
+
+    /// The network delivers the envelope twice.
+    Duplicated {
+        source: BasePeerAddress,
+        destination: BasePeerAddress,
+    },
+
+    /// The envelope reached a crashed peer.
+    Lost {
+        source: BasePeerAddress,
+        destination: BasePeerAddress,
+    },
+
+    /// A partition held the envelope back, see [`crate::partition::CutPolicy`].
+    Withheld {
+        source: BasePeerAddress,
+        destination: BasePeerAddress,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// The simulator delivered an envelope to its destination.
+    Deliver(Envelope),
+    Fault(Fault),
+    /// The simulated environment changed.
+    Change(Change),
+    /// A peer decided.
+    Decide(Decision),
+}
+
+/// A [`TraceEvent`], along with the step of the simulator at which it happened,
+/// and the simulated time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub step: u64,
+    pub time: Duration,
+    pub event: TraceEvent,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trace {
+    pub header: TraceHeader,
+    pub entries: Vec<TraceEntry>,
+}
+
+#[derive(Debug)]
+pub enum TraceError {
+    Io(io::Error),
+    UnsupportedVersion(String),
+    /// The line with the given number, counting from 1, is malformed.
+    Malformed {
+        line: usize,
+        reason: String,
+    },
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceError::Io(e) => write!(f, "{e}"),
+            TraceError::UnsupportedVersion(v) => write!(f, "unsupported trace version {v}"),
+            TraceError::Malformed { line, reason } => write!(f, "line {line}: {reason}"),
+        }
+    }
+}
+
+impl From<io::Error> for TraceError {
+    fn from(e: io::Error) -> Self {
+        TraceError::Io(e)
+    }
+}
+
+impl Trace {
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), TraceError> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_to(&mut file)?;
+        file.flush()?;
+
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Trace, TraceError> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    pub fn write_to(&self, mut w: impl Write) -> io::Result<()> {
+        let header = &self.header;
+        writeln!(w, "{MAGIC} {TRACE_VERSION}")?;
+        writeln!(w, "seed {}", header.seed)?;
+        writeln!(
+            w,
+            "proposer {}",
+            proposer_selection(&header.proposer_selection)
+        )?;
+        writeln!(w, "mock-signatures {}", header.mock_signature_verification)?;
+        writeln!(
+            w,
+            "thresholds {}",
+            thresholds(&header.consensus.threshold_params)
+        )?;
+        writeln!(
+            w,
+            "value-payload {}",
+            value_payload(header.consensus.value_payload)
+        )?;
+        writeln!(w, "validators {}", validators(&header.validators))?;
+        for (from, set) in header.validator_changes.iter() {
+            writeln!(w, "validators-from {from} {}", validators(set))?;
+        }
+
+        for entry in self.entries.iter() {
+            writeln!(w, "{entry}")?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from(r: impl BufRead) -> Result<Trace, TraceError> {
+        let mut lines = r.lines().enumerate();
+        let mut next_line = |field: &str| -> Result<(usize, String), TraceError> {
+            match lines.next() {
+                Some((i, line)) => Ok((i + 1, line?)),
+                None => Err(TraceError::Malformed {
+                    line: 0,
+                    reason: format!("missing {field}"),
+                }),
+            }
+        };
+
+        let (_, magic) = next_line("header")?;
+        match magic.strip_prefix(MAGIC).map(str::trim) {
+            Some(v) if v == TRACE_VERSION.to_string() => {}
+            Some(v) => return Err(TraceError::UnsupportedVersion(v.to_string())),
+            None => {
+                return Err(TraceError::Malformed {
+                    line: 1,
+                    reason: "not a trace".to_string(),
+                })
+            }
+        };
+
+        let mut field = |name: &str| -> Result<(usize, String), TraceError> {
+            let (i, line) = next_line(name)?;
+            match line.strip_prefix(name).and_then(|l| l.strip_prefix(' ')) {
+                Some(value) => Ok((i, value.to_string())),
+                None => Err(TraceError::Malformed {
+                    line: i,
+                    reason: format!("expected {name}"),
+                }),
+            }
+        };
+        let malformed = |line| move |reason| TraceError::Malformed { line, reason };
+
+        let (i, seed) = field("seed")?;
+        let seed = parse(&seed).map_err(malformed(i))?;
+        let (i, proposer) = field("proposer")?;
+        let proposer_selection = parse_proposer_selection(&proposer).map_err(malformed(i))?;
+        let (i, mock) = field("mock-signatures")?;
+        let mock_signature_verification = parse(&mock).map_err(malformed(i))?;
+        let (i, thresholds) = field("thresholds")?;
+        let threshold_params = parse_thresholds(&thresholds).map_err(malformed(i))?;
+        let (i, payload) = field("value-payload")?;
+        let value_payload = parse_value_payload(&payload).map_err(malformed(i))?;
+        let consensus = ConsensusParams {
+            threshold_params,
+            value_payload,
+        };
+        let (i, vals) = field("validators")?;
+        let validators = parse_validators(&vals).map_err(malformed(i))?;
+
+        let mut validator_changes = vec![];
+        let mut entries = vec![];
+        for (i, line) in lines {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match line.strip_prefix("validators-from ") {
+                Some(change) if entries.is_empty() => validator_changes
+                    .push(parse_validator_change(change).map_err(malformed(i + 1))?),
+                _ => entries.push(line.parse().map_err(malformed(i + 1))?),
+            }
+        }
+
+        Ok(Trace {
+            header: TraceHeader {
+                seed,
+                proposer_selection,
+                mock_signature_verification,
+                consensus,
+                validators,
+                validator_changes,
+            },
+            entries,
+        })
+    }
+
+    /// Every decision in the trace, in the order they happened.
+    pub fn decisions(&self) -> impl Iterator<Item = &Decision> {
+        self.entries.iter().filter_map(|e| match &e.event {
+            TraceEvent::Decide(d) => Some(d),
+            _ => None,
+        })
+    }
+
+    /// Checks the decisions in the trace for safety, without executing
+    /// consensus again, see [`SafetyChecker`].
+    /// The values which peers were given to propose are the ones delivered
+    /// to them as `Input::Propose`.
+    pub fn check(&self) -> Vec<ViolationReport> {
+        let mut checker = SafetyChecker::default();
+
+        for entry in self.entries.iter() {
+            match &entry.event {
+                TraceEvent::Deliver(Envelope {
+                    payload: Message::Input(Input::Propose(v)),
+                    ..
+                }) => checker.proposed(v.value.id()),
+                TraceEvent::Decide(d) => {
+                    checker.check(d, entry.step);
+                }
+                _ => {}
+            }
+        }
+
+        checker.violations().to_vec()
+    }
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} ", self.step, self.time.as_nanos())?;
+
+        match &self.event {
+            TraceEvent::Deliver(e) => write!(
+                f,
+                "deliver {} {} {}",
+                e.source.0,
+                e.destination.0,
+                message(&e.payload)
+            ),
+            TraceEvent::Fault(fault) => {
+                let (kind, source, destination) = match fault {
+                    Fault::Dropped {
+                        source,
+                        destination,
+                    } => ("drop", source, destination),
+                    Fault::Duplicated {
+                        source,
+                        destination,
+                    } => ("duplicate", source, destination),
+                    Fault::Lost {
+                        source,
+                        destination,
+                    } => ("lose", source, destination),
+                    Fault::Withheld {
+                        source,
+                        destination,
+                    } => ("withhold", source, destination),
+                };
+                write!(f, "{kind} {} {}", source.0, destination.0)
+            }
+            TraceEvent::Change(Change::Partition(p)) => {
+                write!(f, "partition {}", partition(p.as_ref()))
+            }
+            TraceEvent::Change(Change::PeerMode(peer, mode)) => {
+                write!(f, "mode {} {}", peer.0, peer_mode(mode))
+            }
+            TraceEvent::Decide(d) => write!(
+                f,
+                "decide {} {} {} {} {}",
+                d.peer.0,
+                d.height.0,
+                d.round.as_i64(),
+                d.value_id.0,
+                d.elapsed.as_nanos()
+            ),
+        }
+    }
+}
+
+impl FromStr for TraceEntry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut t = Tokens(s.split_whitespace());
+
+        let step = t.parse()?;
+        let time = Duration::from_nanos(t.parse()?);
+
+        let kind = t.next()?;
+        let event = match kind {
+            "deliver" => TraceEvent::Deliver(Envelope {
+                source: BasePeerAddress(t.parse()?),
+                destination: BasePeerAddress(t.parse()?),
+                payload: parse_message(&mut t)?,
+            }),
+            "drop" | "duplicate" | "lose" | "withhold" => {
+                let source = BasePeerAddress(t.parse()?);
+                let destination = BasePeerAddress(t.parse()?);
+                TraceEvent::Fault(match kind {
+                    "drop" => Fault::Dropped {
+                        source,
+                        destination,
+                    },
+                    "duplicate" => Fault::Duplicated {
+                        source,
+                        destination,
+                    },
+                    "lose" => Fault::Lost {
+                        source,
+                        destination,
+                    },
+                    _ => Fault::Withheld {
+                        source,
+                        destination,
+                    },
+                })
+            }
+            "partition" => TraceEvent::Change(Change::Partition(parse_partition(t.next()?)?)),
+            "mode" => TraceEvent::Change(Change::PeerMode(
+                BasePeerAddress(t.parse()?),
+                parse_peer_mode(t.next()?)?,
+            )),
+            "decide" => TraceEvent::Decide(Decision {
+                peer: BasePeerAddress(t.parse()?),
+                height: BaseHeight(t.parse()?),
+                round: parse_round(t.next()?)?,
+                value_id: BaseValueId(t.parse()?),
+                time,
+                elapsed: Duration::from_nanos(t.parse()?),
+            }),
+            _ => return Err(format!("unknown entry {kind}")),
+        };
+        t.finish()?;
+
+        Ok(TraceEntry { step, time, event })
+    }
+}
+
+// The whitespace-separated tokens of a line.
+struct Tokens<'a>(SplitWhitespace<'a>);
+
+impl<'a> Tokens<'a> {
+    fn next(&mut self) -> Result<&'a str, String> {
+        self.0
+            .next()
+            .ok_or_else(|| "unexpected end of line".to_string())
+    }
+
+    fn parse<T: FromStr>(&mut self) -> Result<T, String> {
+        parse(self.next()?)
+    }
+
+    fn finish(mut self) -> Result<(), String> {
+        match self.0.next() {
+            None => Ok(()),
+            Some(token) => Err(format!("unexpected {token}")),
+        }
+    }
+}
+
+fn parse<T: FromStr>(token: &str) -> Result<T, String> {
+    token.parse().map_err(|_| format!("invalid {token}"))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, b| {
+        let _ = write!(s, "{b:02x}");
+        s
+    })
+}
+
+fn unhex(token: &str) -> Result<Vec<u8>, String> {
+    if !token.len().is_multiple_of(2) {
+        return Err(format!("invalid hexadecimal {token}"));
+    }
+
+    (0..token.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(token.get(i..i + 2).unwrap_or("?"), 16)
+                .map_err(|_| format!("invalid hexadecimal {token}"))
+        })
+        .collect()
+}
+
+// A list of items separated by commas, or `-` if empty.
+fn list(items: impl Iterator<Item = String>) -> String {
+    let items: Vec<_> = items.collect();
+    match items.is_empty() {
+        true => "-".to_string(),
+        false => items.join(","),
+    }
+}
+
+fn parse_list<T>(token: &str, item: impl Fn(&str) -> Result<T, String>) -> Result<Vec<T>, String> {
+    match token {
+        "-" => Ok(vec![]),
+        _ => token.split(',').map(item).collect(),
+    }
+}
+
+fn parse_round(token: &str) -> Result<Round, String> {
+    match parse::<i64>(token)? {
+        -1 => Ok(Round::Nil),
+        r if r >= 0 && r <= u32::MAX as i64 => Ok(Round::new(r as u32)),
+        _ => Err(format!("invalid round {token}")),
+    }
+}
+
+fn signature(token: &str) -> Result<Signature, String> {
+    Signature::try_from(unhex(token)?.as_slice()).map_err(|_| format!("invalid signature {token}"))
+}
+
+fn signed_vote(sv: &SignedVote<BaseContext>) -> String {
+    format!(
+        "{}:{}",
+        hex(&sv.message.to_bytes()),
+        hex(&sv.signature.as_bytes())
+    )
+}
+
+fn parse_signed_vote(token: &str) -> Result<SignedVote<BaseContext>, String> {
+    let (vote, sig) = token.split_once(':').ok_or("expected a signed vote")?;
+    let vote = BaseVote::from_bytes(&unhex(vote)?).map_err(|e| e.to_string())?;
+
+    Ok(SignedMessage::new(vote, signature(sig)?))
+}
+
+fn signed_proposal(sp: &SignedProposal<BaseContext>) -> String {
+    format!(
+        "{}:{}",
+        hex(&sp.message.to_bytes()),
+        hex(&sp.signature.as_bytes())
+    )
+}
+
+fn parse_signed_proposal(token: &str) -> Result<SignedProposal<BaseContext>, String> {
+    let (proposal, sig) = token.split_once(':').ok_or("expected a signed proposal")?;
+    let proposal = BaseProposal::from_bytes(&unhex(proposal)?).map_err(|e| e.to_string())?;
+
+    Ok(SignedMessage::new(proposal, signature(sig)?))
+}
+
+fn signed_part(sp: &SignedProposalPart<BaseContext>) -> String {
+    format!(
+        "{}:{}",
+        hex(&sp.message.to_bytes()),
+        hex(&sp.signature.as_bytes())
+    )
+}
+
+fn parse_signed_part(token: &str) -> Result<SignedProposalPart<BaseContext>, String> {
+    let (part, sig) = token.split_once(':').ok_or("expected a signed part")?;
+    let part = BaseProposalPart::from_bytes(&unhex(part)?).map_err(|e| e.to_string())?;
+
+    Ok(SignedMessage::new(part, signature(sig)?))
+}
+
+fn extension(ext: Option<&SignedExtension<BaseContext>>) -> String {
+    match ext {
+        None => "-".to_string(),
+        Some(ext) => format!(
+            "{}:{}",
+            hex(&ext.message.data),
+            hex(&ext.signature.as_bytes())
+        ),
+    }
+}
+
+fn parse_extension(token: &str) -> Result<Option<SignedExtension<BaseContext>>, String> {
+    if token == "-" {
+        return Ok(None);
+    }
+    let (data, sig) = token.split_once(':').ok_or("expected an extension")?;
+
+    Ok(Some(SignedMessage::new(
+        Extension::new(Bytes::from(unhex(data)?)),
+        signature(sig)?,
+    )))
+}
+
+fn timeout_kind(kind: TimeoutKind) -> &'static str {
+    match kind {
+        TimeoutKind::Propose => "propose",
+        TimeoutKind::Prevote => "prevote",
+        TimeoutKind::PrevoteTimeLimit => "prevote-time-limit",
+        TimeoutKind::Precommit => "precommit",
+        TimeoutKind::PrecommitTimeLimit => "precommit-time-limit",
+        TimeoutKind::Commit => "commit",
+    }
+}
+
+fn parse_timeout_kind(token: &str) -> Result<TimeoutKind, String> {
+    [
+        TimeoutKind::Propose,
+        TimeoutKind::Prevote,
+        TimeoutKind::PrevoteTimeLimit,
+        TimeoutKind::Precommit,
+        TimeoutKind::PrecommitTimeLimit,
+        TimeoutKind::Commit,
+    ]
+    .into_iter()
+    .find(|k| timeout_kind(*k) == token)
+    .ok_or_else(|| format!("unknown timeout {token}"))
+}
+
+fn validators(set: &BasePeerSet) -> String {
+    list(set.peers().iter().map(|p| {
+        format!(
+            "{}:{}:{}",
+            p.id.0,
+            hex(&p.public_key.as_bytes()),
+            p.voting_power
+        )
+    }))
+}
+
+fn parse_validators(token: &str) -> Result<BasePeerSet, String> {
+    let peers = parse_list(token, |peer| {
+        let (id, rest) = peer.split_once(':').ok_or("expected a validator")?;
+        let (key, power) = rest.split_once(':').ok_or("expected a voting power")?;
+        let public_key = PublicKey::try_from(unhex(key)?.as_slice())
+            .map_err(|_| format!("invalid public key {key}"))?;
+
+        Ok(BasePeer::with_voting_power(
+            parse(id)?,
+            public_key,
+            parse(power)?,
+        ))
+    })?;
+
+    // Simulations have at least one peer, and heights at least one validator
+    if peers.is_empty() {
+        return Err("no validator".to_string());
+    }
+
+    Ok(peers.into())
+}
+
+fn parse_validator_change(token: &str) -> Result<(BaseHeight, BasePeerSet), String> {
+    let (from, set) = token.split_once(' ').ok_or("expected a height")?;
+
+    Ok((BaseHeight(parse(from)?), parse_validators(set)?))
+}
+
+fn thresholds(params: &ThresholdParams) -> String {
+    let (quorum, honest) = (params.quorum, params.honest);
+
+    format!(
+        "{}/{} {}/{}",
+        quorum.numerator, quorum.denominator, honest.numerator, honest.denominator
+    )
+}
+
+fn parse_thresholds(token: &str) -> Result<ThresholdParams, String> {
+    let threshold = |t: &str| -> Result<ThresholdParam, String> {
+        let (numerator, denominator) = t.split_once('/').ok_or("expected a fraction")?;
+        Ok(ThresholdParam::new(parse(numerator)?, parse(denominator)?))
+    };
+    let (quorum, honest) = token.split_once(' ').ok_or("expected two thresholds")?;
+
+    Ok(ThresholdParams {
+        quorum: threshold(quorum)?,
+        honest: threshold(honest)?,
+    })
+}
+
+fn value_payload(payload: ValuePayload) -> &'static str {
+    match payload {
+        ValuePayload::ProposalOnly => "proposal-only",
+        ValuePayload::PartsOnly => "parts-only",
+        ValuePayload::ProposalAndParts => "proposal-and-parts",
+    }
+}
+
+fn parse_value_payload(token: &str) -> Result<ValuePayload, String> {
+    [
+        ValuePayload::ProposalOnly,
+        ValuePayload::PartsOnly,
+        ValuePayload::ProposalAndParts,
+    ]
+    .into_iter()
+    .find(|p| value_payload(*p) == token)
+    .ok_or_else(|| format!("unknown value payload {token}"))
+}
+
+fn proposer_selection(selection: &ProposerSelection) -> String {
+    match selection {
+        ProposerSelection::Fixed => "fixed".to_string(),
+        ProposerSelection::RoundRobin => "round-robin".to_string(),
+        ProposerSelection::WeightedPriority => "weighted-priority".to_string(),
+        ProposerSelection::SeededRandom(seed) => format!("seeded-random:{seed}"),
+    }
+}
+
+fn parse_proposer_selection(token: &str) -> Result<ProposerSelection, String> {
+    match token.split_once(':') {
+        None if token == "fixed" => Ok(ProposerSelection::Fixed),
+        None if token == "round-robin" => Ok(ProposerSelection::RoundRobin),
+        None if token == "weighted-priority" => Ok(ProposerSelection::WeightedPriority),
+        Some(("seeded-random", seed)) => Ok(ProposerSelection::SeededRandom(parse(seed)?)),
+        _ => Err(format!("unknown proposer selection {token}")),
+    }
+}
+
+// Groups are separated by `|`, peers within a group by commas.
+fn partition(p: Option<&Partition>) -> String {
+    match p {
+        None => "none".to_string(),
+        Some(p) => p
+            .groups()
+            .iter()
+            .map(|g| list(g.iter().map(|peer| peer.to_string())))
+            .collect::<Vec<_>>()
+            .join("|"),
+    }
+}
+
+fn parse_partition(token: &str) -> Result<Option<Partition>, String> {
+    if token == "none" {
+        return Ok(None);
+    }
+    let groups = token
+        .split('|')
+        .map(|g| parse_list(g, parse))
+        .collect::<Result<_, _>>()?;
+
+    Ok(Some(Partition::new(groups)))
+}
+
+fn peer_mode(mode: &PeerMode) -> String {
+    match mode {
+        PeerMode::Running => "running".to_string(),
+        PeerMode::Crashed => "crashed".to_string(),
+        PeerMode::Silent => "silent".to_string(),
+        PeerMode::Slow(extra) => format!("slow:{}", extra.as_nanos()),
+    }
+}
+
+fn parse_peer_mode(token: &str) -> Result<PeerMode, String> {
+    match token.split_once(':') {
+        None if token == "running" => Ok(PeerMode::Running),
+        None if token == "crashed" => Ok(PeerMode::Crashed),
+        None if token == "silent" => Ok(PeerMode::Silent),
+        Some(("slow", extra)) => Ok(PeerMode::Slow(Duration::from_nanos(parse(extra)?))),
+        _ => Err(format!("unknown peer mode {token}")),
+    }
+}
+
+// The tokens of a message: the kind of its input or `part`, then its fields.
+fn message(message: &Message) -> String {
+    match message {
+        Message::Input(i) => input(i),
+        Message::ProposalPart(sp) => format!("part {}", signed_part(sp)),
+    }
+}
+
+fn parse_message(t: &mut Tokens) -> Result<Message, String> {
+    match t.next()? {
+        "part" => Ok(Message::ProposalPart(parse_signed_part(t.next()?)?)),
+        kind => parse_input(kind, t).map(Message::Input),
+    }
+}
+
+// The tokens of an input: its kind, then its fields.
+fn input(input: &Input<BaseContext>) -> String {
+    let fields: Vec<String> = match input {
+        Input::StartHeight(h, set) => {
+            vec!["start-height".to_string(), h.0.to_string(), validators(set)]
+        }
+        Input::Vote(sv) => vec!["vote".to_string(), signed_vote(sv)],
+        Input::Proposal(sp) => vec!["proposal".to_string(), signed_proposal(sp)],
+        Input::Propose(v) => vec![
+            "propose".to_string(),
+            v.height.0.to_string(),
+            v.round.as_i64().to_string(),
+            v.valid_round.as_i64().to_string(),
+            v.value.0.to_string(),
+            extension(v.extension.as_ref()),
+        ],
+        Input::TimeoutElapsed(t) => vec![
+            "timeout".to_string(),
+            timeout_kind(t.kind).to_string(),
+            t.round.as_i64().to_string(),
+        ],
+        Input::ProposedValue(v, origin) => vec![
+            "proposed-value".to_string(),
+            v.height.0.to_string(),
+            v.round.as_i64().to_string(),
+            v.valid_round.as_i64().to_string(),
+            v.proposer.0.to_string(),
+            v.value.0.to_string(),
+            match v.validity {
+                Validity::Valid => "valid",
+                Validity::Invalid => "invalid",
+            }
+            .to_string(),
+            extension(v.extension.as_ref()),
+            match origin {
+                ValueOrigin::Consensus => "consensus",
+                ValueOrigin::Sync => "sync",
+            }
+            .to_string(),
+        ],
+        Input::CommitCertificate(c) => vec![
+            "certificate".to_string(),
+            c.height.0.to_string(),
+            c.round.as_i64().to_string(),
+            c.value_id.0.to_string(),
+            list(c.aggregated_signature.signatures.iter().map(|s| {
+                let sig = format!("{}:{}", s.address.0, hex(&s.signature.as_bytes()));
+                match &s.extension {
+                    None => sig,
+                    Some(ext) => format!("{sig}:{}", extension(Some(ext))),
+                }
+            })),
+        ],
+        Input::VoteSetRequest(id, h, r) => vec![
+            "vote-set-request".to_string(),
+            hex(id.as_bytes()),
+            h.0.to_string(),
+            r.as_i64().to_string(),
+        ],
+        Input::VoteSetResponse(vote_set) => vec![
+            "vote-set-response".to_string(),
+            list(vote_set.votes.iter().map(signed_vote)),
+        ],
+    };
+
+    fields.join(" ")
+}
+
+fn parse_input(kind: &str, t: &mut Tokens) -> Result<Input<BaseContext>, String> {
+    let input = match kind {
+        "start-height" => Input::StartHeight(BaseHeight(t.parse()?), parse_validators(t.next()?)?),
+        "vote" => Input::Vote(parse_signed_vote(t.next()?)?),
+        "proposal" => Input::Proposal(parse_signed_proposal(t.next()?)?),
+        "propose" => Input::Propose(ValueToPropose {
+            height: BaseHeight(t.parse()?),
+            round: parse_round(t.next()?)?,
+            valid_round: parse_round(t.next()?)?,
+            value: BaseValue(t.parse()?),
+            extension: parse_extension(t.next()?)?,
+        }),
+        "timeout" => Input::TimeoutElapsed(Timeout {
+            kind: parse_timeout_kind(t.next()?)?,
+            round: parse_round(t.next()?)?,
+        }),
+        "proposed-value" => {
+            let value = ProposedValue {
+                height: BaseHeight(t.parse()?),
+                round: parse_round(t.next()?)?,
+                valid_round: parse_round(t.next()?)?,
+                proposer: BasePeerAddress(t.parse()?),
+                value: BaseValue(t.parse()?),
+                validity: match t.next()? {
+                    "valid" => Validity::Valid,
+                    "invalid" => Validity::Invalid,
+                    v => return Err(format!("unknown validity {v}")),
+                },
+                extension: parse_extension(t.next()?)?,
+            };
+            let origin = match t.next()? {
+                "consensus" => ValueOrigin::Consensus,
+                "sync" => ValueOrigin::Sync,
+                o => return Err(format!("unknown origin {o}")),
+            };
+            Input::ProposedValue(value, origin)
+        }
+        "certificate" => {
+            let height = BaseHeight(t.parse()?);
+            let round = parse_round(t.next()?)?;
+            let value_id = BaseValueId(t.parse()?);
+            let signatures = parse_list(t.next()?, |s| {
+                let mut parts = s.splitn(3, ':');
+                let address = BasePeerAddress(parse(parts.next().unwrap_or_default())?);
+                let sig = signature(parts.next().ok_or("expected a signature")?)?;
+                let ext = parts.next().map(parse_extension).transpose()?.flatten();
+
+                Ok(CommitSignature::new(address, sig, ext))
+            })?;
+
+            Input::CommitCertificate(CommitCertificate {
+                height,
+                round,
+                value_id,
+                aggregated_signature: AggregatedSignature::new(signatures),
+            })
+        }
+        "vote-set-request" => {
+            let id = String::from_utf8(unhex(t.next()?)?).map_err(|e| e.to_string())?;
+            Input::VoteSetRequest(id, BaseHeight(t.parse()?), parse_round(t.next()?)?)
+        }
+        "vote-set-response" => {
+            Input::VoteSetResponse(VoteSet::new(parse_list(t.next()?, parse_signed_vote)?))
+        }
+        _ => return Err(format!("unknown input {kind}")),
+    };
+
+    Ok(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use malachite_core_consensus::Input;
+    use malachite_core_types::{Round, SigningProvider, Timeout, TimeoutKind};
+
+    use crate::context::address::BasePeerAddress;
+    use crate::context::height::BaseHeight;
+    use crate::context::proposals::{BaseProposalPart, PartContent};
+    use crate::context::signing_provider::BaseSigningProvider;
+    use crate::partition::Partition;
+    use crate::simulator::{Change, Envelope, Message, PeerMode};
+    use crate::trace::{hex, Trace, TraceEntry, TraceError, TraceEvent};
+
+    #[test]
+    fn entries_round_trip_and_errors_point_at_the_line() {
+        let entries = [
+            TraceEvent::Deliver(Envelope {
+                source: BasePeerAddress(1),
+                destination: BasePeerAddress(1),
+                payload: Message::Input(Input::TimeoutElapsed(Timeout::new(
+                    Round::new(2),
+                    TimeoutKind::Prevote,
+                ))),
+            }),
+            TraceEvent::Deliver(Envelope {
+                source: BasePeerAddress(2),
+                destination: BasePeerAddress(0),
+                payload: Message::ProposalPart(
+                    BaseSigningProvider::derive(0, 2).sign_proposal_part(BaseProposalPart {
+                        height: BaseHeight(3),
+                        round: Round::new(0),
+                        proposer: BasePeerAddress(2),
+                        sequence: 1,
+                        content: PartContent::Data(vec![0, 0, 45]),
+                    }),
+                ),
+            }),
+            TraceEvent::Change(Change::Partition(Some(Partition::new(vec![
+                vec![1, 0],
+                vec![2],
+            ])))),
+            TraceEvent::Change(Change::Partition(None)),
+            TraceEvent::Change(Change::PeerMode(
+                BasePeerAddress(3),
+                PeerMode::Slow(Duration::from_millis(5)),
+            )),
+        ];
+
+        for event in entries {
+            let entry = TraceEntry {
+                step: 12,
+                time: Duration::from_millis(400),
+                event,
+            };
+            assert_eq!(entry.to_string().parse(), Ok(entry));
+        }
+
+        let key = hex(&BaseSigningProvider::derive(0, 0).public_key().as_bytes());
+        let header = format!(
+            "malachite-simulator-trace 1\nseed 0\nproposer fixed\nmock-signatures false\n\
+             thresholds 2/3 1/3\nvalue-payload proposal-only\nvalidators 0:{key}:1\n"
+        );
+        assert!(Trace::read_from(header.as_bytes()).is_ok());
+
+        let bad = format!("{header}3 0 decide 1 0 0 45 0\n3 0 deliver 1 2 nothing\n");
+        assert!(matches!(
+            Trace::read_from(bad.as_bytes()),
+            Err(TraceError::Malformed { line: 9, .. })
+        ));
+
+        // Replays need at least one peer
+        let empty = header.replace(&format!("0:{key}:1"), "-");
+        assert!(matches!(
+            Trace::read_from(empty.as_bytes()),
+            Err(TraceError::Malformed { line: 7, .. })
+        ));
+
+        let future = header.replace("trace 1", "trace 2");
+        assert!(matches!(
+            Trace::read_from(future.as_bytes()),
+            Err(TraceError::UnsupportedVersion(_))
+        ));
+    }
+}
@@ -0,0 +1,75 @@
+/// Changes of the validator set from one height to the next.
+///
+use std::collections::BTreeMap;
+
+use malachite_core_types::{ValidatorSet, VotingPower};
+
+use crate::context::address::BasePeerAddress;
+use crate::context::height::BaseHeight;
+use crate::context::peer::BasePeer;
+use crate::context::peer_set::BasePeerSet;
+
+/// The validators of each height: the initial validator set, until the first
+/// change, then the set of each change from its height until the next change.
+///
+/// Every peer in the simulation follows consensus at every height, but only
+/// the validators of a height vote and propose at that height.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidatorSchedule {
+    initial: BasePeerSet,
+    changes: BTreeMap<BaseHeight, BasePeerSet>,
+}
+
+impl ValidatorSchedule {
+    /// The same validators, `initial`, at every height.
+    pub fn new(initial: BasePeerSet) -> Self {
+        Self {
+            initial,
+            changes: BTreeMap::new(),
+        }
+    }
+
+    /// From `height` onward, until the next change, the validators are `set`.
+    /// Replaces any change at the same height.
+    pub fn change(&mut self, height: BaseHeight, set: BasePeerSet) {
+        self.changes.insert(height, set);
+    }
+
+    /// The validators at `height`.
+    pub fn at(&self, height: BaseHeight) -> &BasePeerSet {
+        self.changes
+            .range(..=height)
+            .next_back()
+            .map_or(&self.initial, |(_, set)| set)
+    }
+
+    pub fn is_validator(&self, peer: &BasePeerAddress, height: BaseHeight) -> bool {
+        self.at(height).get_by_address(peer).is_some()
+    }
+
+    /// Every change, by increasing height.
+    pub fn changes(&self) -> impl Iterator<Item = (&BaseHeight, &BasePeerSet)> {
+        self.changes.iter()
+    }
+}
+
+/// A validator set of some of the peers in `peers`, with their public keys
+/// there and the given voting powers, ordered by address.
+///
+/// Panics if some validator is not in `peers`, or if the validators have
+/// no voting power at all.
+pub fn subset(peers: &BasePeerSet, validators: &[(BasePeerAddress, VotingPower)]) -> BasePeerSet {
+    let mut validators = validators.to_vec();
+    validators.sort_by_key(|(address, _)| *address);
+
+    validators
+        .into_iter()
+        .map(|(address, voting_power)| {
+            let peer = peers
+                .get_by_address(&address)
+                .unwrap_or_else(|| panic!("no peer {address}"));
+            BasePeer::with_voting_power(address.0, peer.public_key, voting_power)
+        })
+        .collect::<Vec<_>>()
+        .into()
+}